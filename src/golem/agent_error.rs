@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+use crate::golem::{AgentId, AgentType};
+
+// Why an invocation didn't produce a result. Whether it ends up as a tool error the model can react to,
+// or as a protocol error, is up to the adaptor
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AgentError {
+    AgentNotFound {
+        agent_id: AgentId,
+    },
+    MethodNotFound {
+        agent_type: AgentType,
+        method_name: String,
+    },
+    InvalidArguments {
+        message: String,
+    },
+    // The agent itself failed, e.g a panic in the guest
+    Trapped {
+        message: String,
+    },
+    Timeout {
+        timeout_ms: Option<u64>,
+    },
+    // The worker executor (or whatever runs the agent) couldn't be reached
+    BackendUnavailable {
+        message: String,
+    },
+    // The backend can't do this at all, unlike `BackendUnavailable` there is no point in retrying
+    Unsupported {
+        message: String,
+    },
+    // Refused before it got to the agent, worth retrying once the time is up
+    RateLimited {
+        message: String,
+        retry_after_ms: u64,
+    },
+    // Too many invocations of the agent are waiting already
+    Overloaded {
+        message: String,
+    },
+}
+
+impl Display for AgentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AgentError::AgentNotFound { agent_id } => write!(f, "agent {} not found", agent_id),
+            AgentError::MethodNotFound {
+                agent_type,
+                method_name,
+            } => {
+                write!(
+                    f,
+                    "method {} not found in agent type {}",
+                    method_name, agent_type
+                )
+            }
+            AgentError::InvalidArguments { message } => write!(f, "invalid arguments: {}", message),
+            AgentError::Trapped { message } => write!(f, "agent failed: {}", message),
+            AgentError::Timeout {
+                timeout_ms: Some(timeout_ms),
+            } => {
+                write!(f, "agent did not respond within {}ms", timeout_ms)
+            }
+            AgentError::Timeout { timeout_ms: None } => write!(f, "agent did not respond in time"),
+            AgentError::BackendUnavailable { message } => {
+                write!(f, "agent backend unavailable: {}", message)
+            }
+            AgentError::Unsupported { message } => write!(f, "not supported: {}", message),
+            AgentError::RateLimited {
+                message,
+                retry_after_ms,
+            } => {
+                write!(
+                    f,
+                    "rate limited: {}, retry after {}ms",
+                    message, retry_after_ms
+                )
+            }
+            AgentError::Overloaded { message } => write!(f, "agent overloaded: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AgentError {}
@@ -0,0 +1,41 @@
+use tokio::sync::broadcast;
+
+use crate::golem::AgentId;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentEvent {
+    // Any invocation that may have changed what the agent's resources return
+    StateChanged(AgentId),
+}
+
+// The invoker backend is the one that knows when an agent's state changed.
+// Anything that wants to react to it (resource subscriptions) subscribes here
+pub trait AgentEventSource: Send + Sync {
+    fn subscribe(&self) -> broadcast::Receiver<AgentEvent>;
+}
+
+// Event source that backends can fire into directly
+#[derive(Clone)]
+pub struct AgentEvents {
+    events: broadcast::Sender<AgentEvent>,
+}
+
+impl Default for AgentEvents {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self { events }
+    }
+}
+
+impl AgentEvents {
+    pub fn state_changed(&self, agent_id: AgentId) {
+        // No subscribers is not an error, it just means no one is connected yet
+        let _ = self.events.send(AgentEvent::StateChanged(agent_id));
+    }
+}
+
+impl AgentEventSource for AgentEvents {
+    fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.events.subscribe()
+    }
+}
@@ -0,0 +1,257 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::golem::{get_agent_type, AgentError, AgentId, AgentType, DataSchema};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentProgress {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+// Handed to the backend for the duration of one invocation.
+// Reporting is a no-op when the client didn't ask for progress
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    sender: Option<mpsc::UnboundedSender<AgentProgress>>,
+}
+
+impl ProgressReporter {
+    pub fn new(sender: mpsc::UnboundedSender<AgentProgress>) -> Self {
+        Self { sender: Some(sender) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    pub fn report(&self, progress: f64, total: Option<f64>, message: Option<String>) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(AgentProgress { progress, total, message });
+        }
+    }
+}
+
+// The agent asking the user for confirmation or extra data, in the middle of an invocation
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentElicitation {
+    pub message: String,
+    pub schema: DataSchema,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentElicitationResponse {
+    // Values for the requested schema
+    Accepted(Map<String, Value>),
+    // The user said no, but the invocation can carry on
+    Declined,
+    // The user dismissed the request without making a choice
+    Cancelled,
+}
+
+// Implemented by whoever can reach the user, i.e the mcp adaptor
+#[async_trait]
+pub trait AgentElicitor: Send + Sync {
+    async fn elicit(&self, elicitation: AgentElicitation) -> Result<AgentElicitationResponse, String>;
+}
+
+// Handed to the backend for the duration of one invocation.
+// Disabled when the client can't elicit, in which case backends should fall back to their defaults (or fail)
+#[derive(Clone, Default)]
+pub struct ElicitationRequester {
+    elicitor: Option<Arc<dyn AgentElicitor>>,
+}
+
+impl ElicitationRequester {
+    pub fn new(elicitor: Arc<dyn AgentElicitor>) -> Self {
+        Self { elicitor: Some(elicitor) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.elicitor.is_some()
+    }
+
+    pub async fn elicit(&self, message: String, schema: DataSchema) -> Result<AgentElicitationResponse, String> {
+        match &self.elicitor {
+            Some(elicitor) => elicitor.elicit(AgentElicitation { message, schema }).await,
+            None => Err("the client does not support elicitation".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentSamplingRole {
+    User,
+    Assistant,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentSamplingMessage {
+    pub role: AgentSamplingRole,
+    pub text: String,
+}
+
+// Priorities are between 0 and 1, hints are model names (or families) in order of preference
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AgentModelPreferences {
+    pub hints: Vec<String>,
+    pub cost_priority: Option<f32>,
+    pub speed_priority: Option<f32>,
+    pub intelligence_priority: Option<f32>,
+}
+
+// An llm completion the agent wants, without holding its own api keys
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentSamplingRequest {
+    pub messages: Vec<AgentSamplingMessage>,
+    pub system_prompt: Option<String>,
+    pub model_preferences: Option<AgentModelPreferences>,
+    pub temperature: Option<f32>,
+    pub max_tokens: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentSamplingResponse {
+    pub model: String,
+    pub stop_reason: Option<String>,
+    pub text: String,
+}
+
+// Implemented by whoever has access to an llm, i.e the mcp adaptor (through the client)
+#[async_trait]
+pub trait AgentSampler: Send + Sync {
+    async fn sample(&self, request: AgentSamplingRequest) -> Result<AgentSamplingResponse, String>;
+}
+
+// Same as `ElicitationRequester`, disabled when the client can't sample
+#[derive(Clone, Default)]
+pub struct SamplingRequester {
+    sampler: Option<Arc<dyn AgentSampler>>,
+}
+
+impl SamplingRequester {
+    pub fn new(sampler: Arc<dyn AgentSampler>) -> Self {
+        Self { sampler: Some(sampler) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sampler.is_some()
+    }
+
+    pub async fn sample(&self, request: AgentSamplingRequest) -> Result<AgentSamplingResponse, String> {
+        match &self.sampler {
+            Some(sampler) => sampler.sample(request).await,
+            None => Err("the client does not support sampling".to_string()),
+        }
+    }
+}
+
+pub struct AgentInvocation {
+    pub agent_id: AgentId,
+    pub method_name: String,
+    pub parameters: Map<String, Value>,
+    pub progress: ProgressReporter,
+    pub elicitation: ElicitationRequester,
+    pub sampling: SamplingRequester,
+    // Cancelled when the client gives up on the request. The invocation future is dropped
+    // right after, so backends only need to look at it to cancel work running elsewhere
+    pub cancellation: CancellationToken,
+    // Where the invocation sits in the caller's trace, for the backend to continue it
+    pub trace_context: Option<TraceContext>,
+}
+
+// W3C trace context, as the `traceparent` and `tracestate` header values
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceContext {
+    pub traceparent: String,
+    pub tracestate: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    Idle,
+    Running,
+    Suspended,
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AgentInfo {
+    pub agent_id: AgentId,
+    pub agent_type: AgentType,
+    pub status: AgentStatus,
+}
+
+// The boundary between the mcp adaptor and golem's worker executor.
+// Lifecycle operations are optional, backends that can't manage agents just don't implement them
+#[async_trait]
+pub trait AgentInvoker: Send + Sync {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError>;
+
+    // Whether the backend can be reached at all, for readiness checks
+    async fn check_health(&self) -> Result<(), AgentError> {
+        Ok(())
+    }
+
+    async fn create_agent(&self, _agent_id: &AgentId) -> Result<AgentInfo, AgentError> {
+        Err(lifecycle_not_supported())
+    }
+
+    async fn list_agents(&self, _agent_type: &AgentType) -> Result<Vec<AgentInfo>, AgentError> {
+        Err(lifecycle_not_supported())
+    }
+
+    async fn get_agent_status(&self, _agent_id: &AgentId) -> Result<AgentInfo, AgentError> {
+        Err(lifecycle_not_supported())
+    }
+
+    async fn delete_agent(&self, _agent_id: &AgentId) -> Result<(), AgentError> {
+        Err(lifecycle_not_supported())
+    }
+}
+
+fn lifecycle_not_supported() -> AgentError {
+    AgentError::Unsupported {
+        message: "the backend does not support agent lifecycle operations".to_string(),
+    }
+}
+
+// Until this is ported to golem there is nothing to invoke
+#[derive(Clone, Default)]
+pub struct DummyAgentInvoker;
+
+#[async_trait]
+impl AgentInvoker for DummyAgentInvoker {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError> {
+        tracing::debug!(method_name = %invocation.method_name, trace_context = ?invocation.trace_context, "agent invoked");
+        invocation.progress.report(1.0, Some(1.0), Some(format!("invoked {}", invocation.method_name)));
+        Ok(json!({"result": "example output"}))
+    }
+
+    // Agents come and go with their invocations here, so any agent exists and there are none to list
+    async fn create_agent(&self, agent_id: &AgentId) -> Result<AgentInfo, AgentError> {
+        self.get_agent_status(agent_id).await
+    }
+
+    async fn list_agents(&self, _agent_type: &AgentType) -> Result<Vec<AgentInfo>, AgentError> {
+        Ok(vec![])
+    }
+
+    async fn get_agent_status(&self, agent_id: &AgentId) -> Result<AgentInfo, AgentError> {
+        Ok(AgentInfo {
+            agent_id: agent_id.clone(),
+            agent_type: get_agent_type(agent_id),
+            status: AgentStatus::Idle,
+        })
+    }
+
+    async fn delete_agent(&self, _agent_id: &AgentId) -> Result<(), AgentError> {
+        Ok(())
+    }
+}
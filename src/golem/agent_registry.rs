@@ -0,0 +1,211 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+
+use crate::golem::{get_agent_type, redact_agent_id, AgentId, AgentMethod, AgentMethodHints, AgentMethodTaskSupport, AgentType, AgentTypeDefinition, DataSchema, DataSchemaEntry, ElementSchema};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AgentRegistryEvent {
+    // A new agent type, or a redeployment that changed its methods
+    AgentTypeDeployed(AgentType),
+    AgentTypeRemoved(AgentType),
+}
+
+impl AgentRegistryEvent {
+    pub fn agent_type(&self) -> &AgentType {
+        match self {
+            AgentRegistryEvent::AgentTypeDeployed(agent_type) => agent_type,
+            AgentRegistryEvent::AgentTypeRemoved(agent_type) => agent_type,
+        }
+    }
+}
+
+// Stand-in for the component/agent-type service in golem. Deployments go through here,
+// and anyone interested in the change (mcp servers rebuilding their routers) subscribes to it
+#[derive(Clone)]
+pub struct AgentRegistry {
+    agent_types: Arc<RwLock<HashMap<AgentType, AgentTypeDefinition>>>,
+    // Agents (instances) we know of, per agent type. Only used for suggestions, so it doesn't have to be complete
+    agents: Arc<RwLock<HashMap<AgentType, BTreeSet<AgentId>>>>,
+    events: broadcast::Sender<AgentRegistryEvent>,
+}
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+impl AgentRegistry {
+    pub fn new(agent_types: HashMap<AgentType, AgentTypeDefinition>) -> Self {
+        let (events, _) = broadcast::channel(64);
+
+        Self {
+            agent_types: Arc::new(RwLock::new(agent_types)),
+            agents: Arc::new(RwLock::new(HashMap::new())),
+            events,
+        }
+    }
+
+    pub fn get_agent_types(&self) -> Vec<AgentType> {
+        let mut agent_types = self
+            .agent_types
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        agent_types.sort();
+        agent_types
+    }
+
+    pub fn get_agent_methods(&self, agent_type: &AgentType) -> Vec<AgentMethod> {
+        self.agent_types
+            .read()
+            .unwrap()
+            .get(agent_type)
+            .map(|definition| definition.methods.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_constructor_schema(&self, agent_type: &AgentType) -> Option<DataSchema> {
+        self.agent_types
+            .read()
+            .unwrap()
+            .get(agent_type)
+            .map(|definition| definition.constructor_schema.clone())
+    }
+
+    // For audit records, spans and logs. Ids of unknown agent types are left as they are, nothing says they hold secrets
+    pub fn redact_agent_id(&self, agent_id: &AgentId) -> AgentId {
+        match self.agent_types.read().unwrap().get(&get_agent_type(agent_id)) {
+            Some(definition) => redact_agent_id(agent_id, &definition.constructor_schema),
+            None => agent_id.clone(),
+        }
+    }
+
+    // Emits `AgentTypeDeployed` only if the definition actually changed
+    pub fn deploy(&self, agent_type: AgentType, definition: AgentTypeDefinition) {
+        let changed = {
+            let mut agent_types = self.agent_types.write().unwrap();
+            let changed = agent_types.get(&agent_type) != Some(&definition);
+            agent_types.insert(agent_type.clone(), definition);
+            changed
+        };
+
+        if changed {
+            // No subscribers is not an error, it just means no one is connected yet
+            let _ = self.events.send(AgentRegistryEvent::AgentTypeDeployed(agent_type));
+        }
+    }
+
+    // Swaps the whole catalog at once, so that no one sees half of it. Events are only for what changed,
+    // and returned as well
+    pub fn replace_all(&self, agent_types: HashMap<AgentType, AgentTypeDefinition>) -> Vec<AgentRegistryEvent> {
+        let events = {
+            let mut current = self.agent_types.write().unwrap();
+
+            let mut events = current
+                .keys()
+                .filter(|agent_type| !agent_types.contains_key(*agent_type))
+                .map(|agent_type| AgentRegistryEvent::AgentTypeRemoved(agent_type.clone()))
+                .collect::<Vec<_>>();
+
+            events.extend(
+                agent_types
+                    .iter()
+                    .filter(|(agent_type, definition)| current.get(*agent_type) != Some(definition))
+                    .map(|(agent_type, _)| AgentRegistryEvent::AgentTypeDeployed(agent_type.clone())),
+            );
+
+            *current = agent_types;
+            events
+        };
+
+        for event in &events {
+            if let AgentRegistryEvent::AgentTypeRemoved(agent_type) = event {
+                self.agents.write().unwrap().remove(agent_type);
+            }
+
+            let _ = self.events.send(event.clone());
+        }
+
+        events
+    }
+
+    // A consistent view of every agent type, unlike calling `get_agent_methods` for each of them
+    pub fn get_agent_type_definitions(&self) -> BTreeMap<AgentType, AgentTypeDefinition> {
+        self.agent_types
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(agent_type, definition)| (agent_type.clone(), definition.clone()))
+            .collect()
+    }
+
+    pub fn remove(&self, agent_type: &AgentType) {
+        let removed = self.agent_types.write().unwrap().remove(agent_type).is_some();
+        self.agents.write().unwrap().remove(agent_type);
+
+        if removed {
+            let _ = self
+                .events
+                .send(AgentRegistryEvent::AgentTypeRemoved(agent_type.clone()));
+        }
+    }
+
+    pub fn add_agent(&self, agent_id: &AgentId) {
+        self.agents
+            .write()
+            .unwrap()
+            .entry(get_agent_type(agent_id))
+            .or_default()
+            .insert(agent_id.clone());
+    }
+
+    pub fn remove_agent(&self, agent_id: &AgentId) {
+        if let Some(agents) = self.agents.write().unwrap().get_mut(&get_agent_type(agent_id)) {
+            agents.remove(agent_id);
+        }
+    }
+
+    // Sorted, so that completions are stable
+    pub fn get_agents(&self, agent_type: &AgentType) -> Vec<AgentId> {
+        self.agents
+            .read()
+            .unwrap()
+            .get(agent_type)
+            .map(|agents| agents.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentRegistryEvent> {
+        self.events.subscribe()
+    }
+}
+
+// The only agent type we have in this POC
+pub fn get_counter_agent_type() -> (AgentType, AgentTypeDefinition) {
+    (
+        "counter".into(),
+        AgentTypeDefinition {
+            constructor_schema: vec![DataSchemaEntry::new("id", ElementSchema::U32)],
+            methods: vec![AgentMethod {
+                method_name: "increment".into(),
+                title: Some("Increment counter".into()),
+                description: Some("Increments the counter by the given number and returns the new value".into()),
+                input_schema: vec![DataSchemaEntry::new("number", ElementSchema::U32)],
+                output_schema: vec![DataSchemaEntry::new("result", ElementSchema::U32)],
+                hints: AgentMethodHints {
+                    read_only: Some(false),
+                    destructive: Some(false),
+                    idempotent: Some(false),
+                    open_world: Some(false),
+                },
+                task_support: AgentMethodTaskSupport::Forbidden,
+                exposed_as: vec![],
+            }],
+        },
+    )
+}
@@ -0,0 +1,272 @@
+// Over simplified golem
+
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub use agent_error::*;
+pub use agent_events::*;
+pub use agent_invoker::*;
+pub use agent_registry::*;
+
+mod agent_error;
+mod agent_events;
+mod agent_invoker;
+mod agent_registry;
+
+// What is deployed for an agent type. Agents are constructed with parameters following the
+// constructor schema, which is also what their id is made of (see `get_agent_id`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AgentTypeDefinition {
+    pub constructor_schema: DataSchema,
+    pub methods: Vec<AgentMethod>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentMethod {
+    pub method_name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub input_schema: DataSchema,
+    pub output_schema: DataSchema,
+    pub hints: AgentMethodHints,
+    pub task_support: AgentMethodTaskSupport,
+    // How the agent author wants the method exposed, empty leaves it to the mapping rules of the mcp server
+    pub exposed_as: Vec<AgentMethodExposure>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentMethodExposure {
+    Tool,
+    Resource,
+    Prompt,
+}
+
+// What the method does to the agent (and the world outside of it), if the agent author told us.
+// Clients use these to decide whether a call needs the user's approval
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentMethodHints {
+    pub read_only: Option<bool>,
+    pub destructive: Option<bool>,
+    pub idempotent: Option<bool>,
+    pub open_world: Option<bool>,
+}
+
+// Whether the method can (or has to) run in the background, i.e be called as an mcp task
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentMethodTaskSupport {
+    #[default]
+    Forbidden,
+    Optional,
+    Required,
+}
+
+pub type AgentId = String;
+
+pub type AgentType = String;
+
+pub type ParameterName = String;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ElementSchema {
+    String,
+    U32,
+    Bool,
+    // A string that has to be one of the given values
+    Enum(Vec<String>),
+    // Free form payloads, with the mime type they are restricted to (if any). As a parameter they are a string
+    // (base64 for binary), and in a result either inline `{"data": .., "mime_type": ..}`, where binary data is
+    // base64 or an array of bytes, or a reference `{"uri": .., "mime_type": .., "name": ..}`
+    UnstructuredText { mime_type: Option<String> },
+    UnstructuredBinary { mime_type: Option<String> },
+}
+
+impl ElementSchema {
+    pub fn is_unstructured(&self) -> bool {
+        matches!(self, ElementSchema::UnstructuredText { .. } | ElementSchema::UnstructuredBinary { .. })
+    }
+}
+
+impl Display for ElementSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementSchema::String => write!(f, "a string"),
+            ElementSchema::U32 => write!(f, "an unsigned 32 bit integer"),
+            ElementSchema::Bool => write!(f, "a boolean"),
+            ElementSchema::Enum(values) => write!(f, "one of {}", values.join(", ")),
+            ElementSchema::UnstructuredText { .. } => write!(f, "a text"),
+            ElementSchema::UnstructuredBinary { .. } => write!(f, "a base64 encoded binary"),
+        }
+    }
+}
+
+pub type DataSchema = Vec<DataSchemaEntry>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataSchemaEntry {
+    pub name: ParameterName,
+    pub schema: ElementSchema,
+    // Secrets, personal data and the like. Never written to the audit log, see `redact_data`
+    pub sensitive: bool,
+}
+
+impl DataSchemaEntry {
+    pub fn new(name: impl Into<ParameterName>, schema: ElementSchema) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            sensitive: false,
+        }
+    }
+
+    pub fn with_sensitive(mut self, sensitive: bool) -> Self {
+        self.sensitive = sensitive;
+        self
+    }
+}
+
+pub const REDACTED: &str = "[redacted]";
+
+// What is left to record of the data, sensitive parameters are replaced with `REDACTED`.
+// Anything the schema doesn't know about is dropped, as there is no telling what it holds
+pub fn redact_data(schema: &DataSchema, data: &Map<String, Value>) -> Map<String, Value> {
+    schema
+        .iter()
+        .filter_map(|entry| {
+            let value = data.get(&entry.name)?;
+
+            if entry.sensitive {
+                Some((entry.name.clone(), Value::String(REDACTED.to_string())))
+            } else {
+                Some((entry.name.clone(), value.clone()))
+            }
+        })
+        .collect()
+}
+
+// Every parameter of the schema is required, and unknown ones are ignored
+pub fn validate_data(schema: &DataSchema, data: &Map<String, Value>) -> Result<(), String> {
+    for DataSchemaEntry { name, schema: element_schema, .. } in schema {
+        let valid = match (element_schema, data.get(name)) {
+            (ElementSchema::String, Some(Value::String(_))) => true,
+            (ElementSchema::U32, Some(Value::Number(number))) => {
+                number.as_u64().is_some_and(|number| number <= u32::MAX as u64)
+            }
+            (ElementSchema::Bool, Some(Value::Bool(_))) => true,
+            (ElementSchema::Enum(values), Some(Value::String(value))) => values.contains(value),
+            (ElementSchema::UnstructuredText { .. }, Some(Value::String(_))) => true,
+            (ElementSchema::UnstructuredBinary { .. }, Some(Value::String(_))) => true,
+            _ => false,
+        };
+
+        if !valid {
+            return Err(format!("missing or invalid `{}`, expected {}", name, element_schema));
+        }
+    }
+
+    Ok(())
+}
+
+// Agent ids are of the form `agent-type(constructor params)`, e.g `counter(1)`.
+// An id without constructor params is treated as the agent type itself
+pub fn get_agent_type(agent_id: &AgentId) -> AgentType {
+    match agent_id.split_once('(') {
+        Some((agent_type, _)) => agent_type.to_string(),
+        None => agent_id.clone(),
+    }
+}
+
+// The other way around, e.g `counter` with `{"id": 1}` is `counter(1)`.
+// Params are rendered as json, in the order of the constructor schema
+pub fn get_agent_id(agent_type: &AgentType, constructor_schema: &DataSchema, params: &Map<String, Value>) -> Result<AgentId, String> {
+    validate_data(constructor_schema, params)?;
+
+    let params = constructor_schema
+        .iter()
+        .map(|entry| params[&entry.name].to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    Ok(format!("{}({})", agent_type, params))
+}
+
+// The agent id as it can be recorded, with its sensitive constructor params replaced with `REDACTED`.
+// An id that doesn't follow the constructor schema has all its params redacted, as there is no telling which is which
+pub fn redact_agent_id(agent_id: &AgentId, constructor_schema: &DataSchema) -> AgentId {
+    if !constructor_schema.iter().any(|entry| entry.sensitive) {
+        return agent_id.clone();
+    }
+
+    let agent_type = get_agent_type(agent_id);
+    let params = agent_id
+        .strip_prefix(&format!("{}(", agent_type))
+        .and_then(|params| params.strip_suffix(')'))
+        .and_then(|params| serde_json::from_str::<Vec<Value>>(&format!("[{}]", params)).ok())
+        .filter(|params| params.len() == constructor_schema.len());
+
+    let Some(params) = params else {
+        return format!("{}({})", agent_type, REDACTED);
+    };
+
+    let params = constructor_schema
+        .iter()
+        .zip(params)
+        .map(|(entry, param)| if entry.sensitive { Value::String(REDACTED.to_string()) } else { param }.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("{}({})", agent_type, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> DataSchema {
+        vec![
+            DataSchemaEntry::new("owner", ElementSchema::String),
+            DataSchemaEntry::new("api_key", ElementSchema::String).with_sensitive(true),
+        ]
+    }
+
+    #[test]
+    fn redact_data_replaces_sensitive_values_and_drops_unknown_ones() {
+        let data = json!({"owner": "ann", "api_key": "secret", "extra": "who knows"});
+
+        let redacted = redact_data(&schema(), data.as_object().unwrap());
+
+        assert_eq!(Value::Object(redacted), json!({"owner": "ann", "api_key": REDACTED}));
+    }
+
+    #[test]
+    fn redact_data_leaves_missing_parameters_out() {
+        let redacted = redact_data(&schema(), json!({"owner": "ann"}).as_object().unwrap());
+
+        assert_eq!(Value::Object(redacted), json!({"owner": "ann"}));
+    }
+
+    #[test]
+    fn redact_agent_id_replaces_sensitive_constructor_params() {
+        let params = json!({"owner": "ann, bob", "api_key": "secret"});
+        let agent_id = get_agent_id(&"vault".to_string(), &schema(), params.as_object().unwrap()).unwrap();
+
+        assert_eq!(redact_agent_id(&agent_id, &schema()), r#"vault("ann, bob","[redacted]")"#);
+    }
+
+    #[test]
+    fn redact_agent_id_keeps_ids_without_sensitive_params() {
+        let schema = vec![DataSchemaEntry::new("id", ElementSchema::U32)];
+
+        assert_eq!(redact_agent_id(&"counter(1)".to_string(), &schema), "counter(1)");
+    }
+
+    #[test]
+    fn redact_agent_id_redacts_every_param_of_a_malformed_id() {
+        assert_eq!(redact_agent_id(&r#"vault("secret")"#.to_string(), &schema()), "vault([redacted])");
+        assert_eq!(redact_agent_id(&"vault(secret".to_string(), &schema()), "vault([redacted])");
+    }
+}
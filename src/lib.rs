@@ -5,4 +5,5 @@
 
 pub mod golem;
 pub mod mcp_adaptor;
+pub mod server;
 
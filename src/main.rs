@@ -0,0 +1,102 @@
+// The mcp server as a binary, everything about it comes from the config file (see `ServerConfig`)
+// and `MCP_SERVER__*` environment variables.
+//
+//   mcp-server --config mcp-server.toml
+//   mcp-server --config mcp-server.toml --check-config
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use mcp_server::server::{build_context, check_context, init_logging, run, ServerConfig};
+
+// Used when there is no --config
+const CONFIG_PATH_ENV: &str = "MCP_SERVER_CONFIG";
+
+const USAGE: &str = "usage: mcp-server [--config <path>] [--check-config]";
+
+struct Args {
+    config: Option<PathBuf>,
+    check_config: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        config: std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from),
+        check_config: false,
+    };
+
+    let mut raw_args = std::env::args().skip(1);
+
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "--config" | "-c" => {
+                args.config = Some(raw_args.next().ok_or("--config needs a path")?.into());
+            }
+            "--check-config" => args.check_config = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument `{}`\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = ServerConfig::load(args.config.as_deref()).and_then(|config| config.validate().map(|_| config));
+
+    // Checked without creating anything, the audit log included
+    if args.check_config {
+        return match config.and_then(|config| check_context(&config)) {
+            Ok(()) => {
+                println!("config is valid");
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let context = config.and_then(|config| build_context(&config).map(|context| (config, context)));
+
+    let (config, context) = match context {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let tracer_provider = match init_logging(&config, context.loggers.clone()) {
+        Ok(tracer_provider) => tracer_provider,
+        Err(error) => {
+            eprintln!("failed to set up the otlp exporter: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let exit_code = match run(config, context).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            tracing::error!(%error, "mcp server failed");
+            ExitCode::FAILURE
+        }
+    };
+
+    // Flushes the spans that are yet to be exported
+    if let Err(error) = tracer_provider.shutdown() {
+        eprintln!("failed to shut down the otlp exporter: {}", error);
+    }
+
+    exit_code
+}
@@ -1,27 +1,60 @@
-mod rmcp_streamable_http;
-
 // Refer to rcmp_streamable_http.rs for a more useful stuff
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use axum::{
-    extract::Json,
+    extract::{Json, State},
     http::{HeaderMap, HeaderValue, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
     response::IntoResponse,
     routing::post,
     Router,
 };
+use futures::Stream;
 use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
 
 const BIND_ADDRESS: &str = "127.0.0.1:8000";
 
+// Sessions nobody has used (nor listened to) for this long are dropped, clients rarely bother sending DELETE
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+// Lowest to highest, as in logging/setLevel
+const LOG_LEVELS: [&str; 8] = ["debug", "info", "notice", "warning", "error", "critical", "alert", "emergency"];
+
+struct Session {
+    // Backs the standalone `GET /mcp` stream.
+    // Anything sent before the client opens the stream is dropped, which is allowed by the spec
+    sender: broadcast::Sender<Value>,
+    // No notifications/message until the client sets a level
+    log_level: Option<usize>,
+    last_seen: Instant,
+}
+
+impl Session {
+    fn is_idle(&self, now: Instant) -> bool {
+        self.sender.receiver_count() == 0 && now.duration_since(self.last_seen) > SESSION_IDLE_TIMEOUT
+    }
+}
+
+type Sessions = Arc<RwLock<HashMap<String, Session>>>;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()>  {
     let tcp_listener = tokio::net::TcpListener::bind(BIND_ADDRESS).await?;
 
-    let app = Router::new().route("/mcp", post(mcp_handler));
+    let sessions: Sessions = Arc::new(RwLock::new(HashMap::new()));
+
+    tokio::spawn(expire_sessions(sessions.clone()));
 
+    let app = Router::new()
+        .route("/mcp", post(mcp_handler).get(mcp_stream).delete(mcp_delete))
+        .with_state(sessions);
 
-    println!("MCP server running on http://0.0.0.0:8080/mcp");
+
+    println!("MCP server running on http://{}/mcp", BIND_ADDRESS);
 
     axum::serve(tcp_listener, app.into_make_service())
         .await?;
@@ -29,7 +62,125 @@ async fn main() -> anyhow::Result<()>  {
     Ok(())
 }
 
+async fn expire_sessions(sessions: Sessions) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+        let now = Instant::now();
+        sessions.write().await.retain(|_, session| !session.is_idle(now));
+    }
+}
+
+// Server initiated messages (notifications/tools/list_changed, notifications/message etc)
+// are pushed on this stream, outside of any request
+async fn mcp_stream(
+    State(sessions): State<Sessions>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let session_id = get_session_id(&headers).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let receiver = sessions
+        .read()
+        .await
+        .get(&session_id)
+        .map(|session| session.sender.subscribe())
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => {
+                    let event = Event::default().event("message").data(message.to_string());
+                    return Some((Ok(event), receiver));
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn mcp_delete(
+    State(sessions): State<Sessions>,
+    headers: HeaderMap,
+) -> StatusCode {
+    match get_session_id(&headers) {
+        Some(session_id) if sessions.write().await.remove(&session_id).is_some() => StatusCode::NO_CONTENT,
+        Some(_) => StatusCode::NOT_FOUND,
+        None => StatusCode::BAD_REQUEST,
+    }
+}
+
+// Returns the number of open streams the notification reached
+async fn notify(sessions: &Sessions, session_id: &str, method: &str, params: Value) -> usize {
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params
+    });
+
+    sessions
+        .read()
+        .await
+        .get(session_id)
+        .and_then(|session| session.sender.send(notification).ok())
+        .unwrap_or(0)
+}
+
+// notifications/message, if the session asked for this level or a lower one
+async fn log(sessions: &Sessions, session_id: &str, level: &str, logger: &str, data: Value) -> usize {
+    let enabled = sessions
+        .read()
+        .await
+        .get(session_id)
+        .and_then(|session| session.log_level)
+        .is_some_and(|log_level| get_log_level(level).is_some_and(|level| level >= log_level));
+
+    if !enabled {
+        return 0;
+    }
+
+    notify(
+        sessions,
+        session_id,
+        "notifications/message",
+        json!({
+            "level": level,
+            "logger": logger,
+            "data": data
+        }),
+    ).await
+}
+
+fn get_log_level(level: &str) -> Option<usize> {
+    LOG_LEVELS.iter().position(|l| *l == level)
+}
+
+fn error_response(id: Option<Value>, status: StatusCode, code: i64, message: &str) -> (StatusCode, HeaderMap, Json<Value>) {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message
+        }
+    });
+
+    (status, HeaderMap::new(), Json(body))
+}
+
+fn get_session_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("Mcp-Session-Id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
 async fn mcp_handler(
+    State(sessions): State<Sessions>,
     headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> impl IntoResponse {
@@ -42,13 +193,22 @@ async fn mcp_handler(
     if method == "initialize" || method == "initialise" {
         let session_id = Uuid::new_v4().to_string();
 
+        let (sender, _) = broadcast::channel(64);
+        let session = Session {
+            sender,
+            log_level: None,
+            last_seen: Instant::now(),
+        };
+        sessions.write().await.insert(session_id.clone(), session);
+
         let body = json!({
             "jsonrpc": "2.0",
             "id": id,
             "result": {
                 "protocolVersion": "2025-06-18",
                 "capabilities": {
-                    "tools": { "listChanged": false }
+                    "tools": { "listChanged": false },
+                    "logging": {}
                 },
                 "serverInfo": {
                     "name": "golem-mcp-dev",
@@ -71,17 +231,38 @@ async fn mcp_handler(
         return (StatusCode::ACCEPTED, HeaderMap::new(), Json(json!({})));
     }
 
-    if headers.get("Mcp-Session-Id").is_none() {
+    let Some(session_id) = get_session_id(&headers) else {
+        return error_response(id, StatusCode::BAD_REQUEST, -32000, "Missing Mcp-Session-Id");
+    };
+
+    // An expired (or deleted) session is a 404, upon which the client initializes again
+    match sessions.write().await.get_mut(&session_id) {
+        Some(session) => session.last_seen = Instant::now(),
+        None => return error_response(id, StatusCode::NOT_FOUND, -32001, "Session not found"),
+    }
+
+    if method == "logging/setLevel" {
+        let level = payload
+            .get("params")
+            .and_then(|params| params.get("level"))
+            .and_then(|v| v.as_str())
+            .and_then(get_log_level);
+
+        let Some(level) = level else {
+            return error_response(id, StatusCode::BAD_REQUEST, -32602, "Invalid log level");
+        };
+
+        if let Some(session) = sessions.write().await.get_mut(&session_id) {
+            session.log_level = Some(level);
+        }
+
         let body = json!({
             "jsonrpc": "2.0",
             "id": id,
-            "error": {
-                "code": -32000,
-                "message": "Missing Mcp-Session-Id"
-            }
+            "result": {}
         });
 
-        return (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(body));
+        return (StatusCode::OK, HeaderMap::new(), Json(body));
     }
 
     if method == "tools/list" {
@@ -116,17 +297,9 @@ async fn mcp_handler(
             .and_then(|v| v.as_str())
             .unwrap_or("");
 
+        // An unknown tool is an invalid params error, the method itself exists
         if tool_name != "counter" {
-            let body = json!({
-                "jsonrpc": "2.0",
-                "id": id,
-                "error": {
-                    "code": -32601,
-                    "message": "Tool not found"
-                }
-            });
-
-            return (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(body));
+            return error_response(id, StatusCode::BAD_REQUEST, -32602, "Tool not found");
         }
 
         let args = params
@@ -141,6 +314,14 @@ async fn mcp_handler(
 
         let incremented = number + 1;
 
+        log(
+            &sessions,
+            &session_id,
+            "info",
+            "counter",
+            json!(format!("counter incremented to {}", incremented)),
+        ).await;
+
         let body = json!({
             "jsonrpc": "2.0",
             "id": id,
@@ -158,14 +339,5 @@ async fn mcp_handler(
         return (StatusCode::OK, HeaderMap::new(), Json(body));
     }
 
-    let body = json!({
-        "jsonrpc": "2.0",
-        "id": id,
-        "error": {
-            "code": -32601,
-            "message": "Method not found"
-        }
-    });
-
-    (StatusCode::BAD_REQUEST, HeaderMap::new(), Json(body))
+    error_response(id, StatusCode::BAD_REQUEST, -32601, "Method not found")
 }
@@ -1,24 +1,308 @@
-use crate::golem::AgentMethod;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use crate::golem::{AgentMethod, AgentMethodExposure, AgentType, AgentTypeDefinition};
+use crate::mcp_adaptor::agent_mcp_prompt::AgentMcpPrompt;
 use crate::mcp_adaptor::agent_mcp_resource::AgentMcpResource;
 use crate::mcp_adaptor::agent_mcp_tool::AgentMcpTool;
+use crate::mcp_adaptor::McpServerContext;
 
 #[derive(Clone)]
 pub enum McpAgentCapability {
     Tool(AgentMcpTool),
     Resource(AgentMcpResource),
+    Prompt(AgentMcpPrompt),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpCapabilityKind {
+    Tool,
+    Resource,
+    Prompt,
+}
 
+// Glob like patterns where `*` matches any number of characters, e.g `counter` and `debug_*`.
+// A missing agent type matches every agent type
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpMethodPattern {
+    #[serde(default)]
+    pub agent_type: Option<String>,
+    pub method_name: String,
+}
 
-impl McpAgentCapability {
+impl McpMethodPattern {
+    pub fn new(agent_type: Option<&str>, method_name: &str) -> Self {
+        Self {
+            agent_type: agent_type.map(str::to_string),
+            method_name: method_name.to_string(),
+        }
+    }
+
+    pub fn matches(&self, agent_type: &AgentType, method: &AgentMethod) -> bool {
+        self.agent_type
+            .as_ref()
+            .is_none_or(|pattern| matches_glob(pattern, agent_type))
+            && matches_glob(&self.method_name, &method.method_name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct McpMappingRule {
+    #[serde(flatten)]
+    pub pattern: McpMethodPattern,
+    pub expose_as: Vec<McpCapabilityKind>,
+}
+
+// How agent methods are exposed, in order of precedence:
+// excluded methods are hidden, then whatever the method itself asks for (see `AgentMethod::exposed_as`),
+// then the first matching rule, and otherwise a method with parameters is a tool and one without is a resource
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpMappingRules {
+    #[serde(default)]
+    pub exclusions: Vec<McpMethodPattern>,
+    #[serde(default)]
+    pub rules: Vec<McpMappingRule>,
+}
+
+impl McpMappingRules {
+    pub fn exclude(mut self, pattern: McpMethodPattern) -> Self {
+        self.exclusions.push(pattern);
+        self
+    }
+
+    pub fn with_rule(mut self, pattern: McpMethodPattern, expose_as: Vec<McpCapabilityKind>) -> Self {
+        self.rules.push(McpMappingRule { pattern, expose_as });
+        self
+    }
+
+    pub fn get_capability_kinds(&self, agent_type: &AgentType, method: &AgentMethod) -> Vec<McpCapabilityKind> {
+        if self.exclusions.iter().any(|pattern| pattern.matches(agent_type, method)) {
+            return vec![];
+        }
+
+        if !method.exposed_as.is_empty() {
+            return method
+                .exposed_as
+                .iter()
+                .map(|exposure| match exposure {
+                    AgentMethodExposure::Tool => McpCapabilityKind::Tool,
+                    AgentMethodExposure::Resource => McpCapabilityKind::Resource,
+                    AgentMethodExposure::Prompt => McpCapabilityKind::Prompt,
+                })
+                .collect();
+        }
+
+        if let Some(rule) = self.rules.iter().find(|rule| rule.pattern.matches(agent_type, method)) {
+            return rule.expose_as.clone();
+        }
 
-    // Infallible
-    pub fn from(method: AgentMethod) -> Self {
-        // Based on mapping rules
-        if method.input_schema.len() > 0 {
-            Self::Tool(AgentMcpTool { tool: method })
+        if !method.input_schema.is_empty() {
+            vec![McpCapabilityKind::Tool]
         } else {
-            Self::Resource(AgentMcpResource { resource: method })
+            vec![McpCapabilityKind::Resource]
+        }
+    }
+
+    // Methods asked to be a resource that have parameters, which a resource read has no way to pass.
+    // They are left out of the resources, this is for reporting them once when the rules or agent types are loaded
+    pub fn get_unreadable_resources(&self, agent_types: &BTreeMap<AgentType, AgentTypeDefinition>) -> Vec<(AgentType, String)> {
+        agent_types
+            .iter()
+            .flat_map(|(agent_type, definition)| {
+                definition
+                    .methods
+                    .iter()
+                    .filter(|method| {
+                        !method.input_schema.is_empty()
+                            && self.get_capability_kinds(agent_type, method).contains(&McpCapabilityKind::Resource)
+                    })
+                    .map(|method| (agent_type.clone(), method.method_name.clone()))
+            })
+            .collect()
+    }
+}
+
+impl McpAgentCapability {
+
+    // Infallible, but a method can map to nothing (excluded) or to more than one capability
+    pub fn from(agent_type: &AgentType, method: AgentMethod, rules: &McpMappingRules) -> Vec<Self> {
+        let mut capabilities = vec![];
+
+        for kind in rules.get_capability_kinds(agent_type, &method) {
+            match kind {
+                McpCapabilityKind::Tool => capabilities.push(Self::Tool(AgentMcpTool {
+                    agent_type: agent_type.clone(),
+                    tool: method.clone(),
+                    constructor_schema: None,
+                    management: None,
+                })),
+                // A resource is read with no parameters at all, see `McpMappingRules::get_unreadable_resources`
+                McpCapabilityKind::Resource if !method.input_schema.is_empty() => {}
+                McpCapabilityKind::Resource => {
+                    capabilities.push(Self::Resource(AgentMcpResource { resource: method.clone() }))
+                }
+                McpCapabilityKind::Prompt => {
+                    capabilities.push(Self::Prompt(AgentMcpPrompt {
+                        agent_type: agent_type.clone(),
+                        agent_method: method.clone(),
+                    }))
+                }
+            }
         }
+
+        capabilities
+    }
+}
+
+// Logged rather than failing the server, the methods are still there as whatever else they are exposed as
+pub fn warn_unreadable_resources(context: &McpServerContext) {
+    let agent_types = context.registry.get_agent_type_definitions();
+
+    for (agent_type, method_name) in context.mapping_rules.get_unreadable_resources(&agent_types) {
+        tracing::warn!(%agent_type, %method_name, "method has parameters, not exposing it as a resource");
+    }
+}
+
+fn matches_glob(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == value,
+        Some((prefix, rest)) => {
+            let Some(value) = value.strip_prefix(prefix) else {
+                return false;
+            };
+
+            // Try every possible length for the `*`
+            (0..=value.len())
+                .filter(|i| value.is_char_boundary(*i))
+                .any(|i| matches_glob(rest, &value[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::golem::{AgentMethodHints, AgentMethodTaskSupport, DataSchemaEntry, ElementSchema};
+
+    use super::*;
+
+    fn method(method_name: &str, has_parameters: bool) -> AgentMethod {
+        AgentMethod {
+            method_name: method_name.to_string(),
+            title: None,
+            description: None,
+            input_schema: if has_parameters {
+                vec![DataSchemaEntry::new("number", ElementSchema::U32)]
+            } else {
+                vec![]
+            },
+            output_schema: vec![],
+            hints: AgentMethodHints::default(),
+            task_support: AgentMethodTaskSupport::Forbidden,
+            exposed_as: vec![],
+        }
+    }
+
+    fn kinds(rules: &McpMappingRules, agent_type: &str, method: &AgentMethod) -> Vec<McpCapabilityKind> {
+        rules.get_capability_kinds(&agent_type.to_string(), method)
+    }
+
+    #[test]
+    fn glob_matches_any_number_of_characters() {
+        assert!(matches_glob("debug_*", "debug_dump"));
+        assert!(matches_glob("debug_*", "debug_"));
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("get_*_count", "get_item_count"));
+        assert!(matches_glob("increment", "increment"));
+        assert!(!matches_glob("debug_*", "get_debug"));
+        assert!(!matches_glob("get_*_count", "get_items"));
+        assert!(!matches_glob("increment", "increment_by"));
+    }
+
+    #[test]
+    fn pattern_without_agent_type_matches_every_agent_type() {
+        let any_agent_type = McpMethodPattern::new(None, "get_*");
+        let counter_only = McpMethodPattern::new(Some("counter"), "get_*");
+
+        assert!(any_agent_type.matches(&"timer".to_string(), &method("get_value", false)));
+        assert!(counter_only.matches(&"counter".to_string(), &method("get_value", false)));
+        assert!(!counter_only.matches(&"timer".to_string(), &method("get_value", false)));
+    }
+
+    #[test]
+    fn methods_are_exposed_by_parameters_without_rules() {
+        let rules = McpMappingRules::default();
+
+        assert_eq!(kinds(&rules, "counter", &method("increment", true)), vec![McpCapabilityKind::Tool]);
+        assert_eq!(kinds(&rules, "counter", &method("get_value", false)), vec![McpCapabilityKind::Resource]);
+    }
+
+    #[test]
+    fn exclusions_win_over_the_method_and_the_method_over_rules() {
+        let rules = McpMappingRules::default()
+            .exclude(McpMethodPattern::new(None, "debug_*"))
+            .with_rule(McpMethodPattern::new(None, "*"), vec![McpCapabilityKind::Prompt])
+            .with_rule(McpMethodPattern::new(None, "increment"), vec![McpCapabilityKind::Tool]);
+
+        let mut debug_dump = method("debug_dump", true);
+        debug_dump.exposed_as = vec![AgentMethodExposure::Tool];
+        assert!(kinds(&rules, "counter", &debug_dump).is_empty());
+
+        let mut get_value = method("get_value", false);
+        get_value.exposed_as = vec![AgentMethodExposure::Resource, AgentMethodExposure::Tool];
+        assert_eq!(
+            kinds(&rules, "counter", &get_value),
+            vec![McpCapabilityKind::Resource, McpCapabilityKind::Tool]
+        );
+
+        // The first matching rule, not the most specific one
+        assert_eq!(kinds(&rules, "counter", &method("increment", true)), vec![McpCapabilityKind::Prompt]);
+    }
+
+    #[test]
+    fn resources_with_parameters_are_reported_and_left_out() {
+        let rules = McpMappingRules::default().with_rule(
+            McpMethodPattern::new(None, "*"),
+            vec![McpCapabilityKind::Tool, McpCapabilityKind::Resource],
+        );
+        let definition = AgentTypeDefinition {
+            constructor_schema: vec![],
+            methods: vec![method("increment", true), method("get_value", false)],
+        };
+        let agent_types = BTreeMap::from([("counter".to_string(), definition)]);
+
+        assert_eq!(
+            rules.get_unreadable_resources(&agent_types),
+            vec![("counter".to_string(), "increment".to_string())]
+        );
+
+        let capabilities = McpAgentCapability::from(&"counter".to_string(), method("increment", true), &rules);
+        assert!(matches!(capabilities.as_slice(), [McpAgentCapability::Tool(_)]));
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let rules: McpMappingRules = toml::from_str(
+            r#"
+            exclusions = [{ method_name = "debug_*" }]
+
+            [[rules]]
+            agent_type = "counter"
+            method_name = "get_*"
+            expose_as = ["resource"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules,
+            McpMappingRules::default()
+                .exclude(McpMethodPattern::new(None, "debug_*"))
+                .with_rule(McpMethodPattern::new(Some("counter"), "get_*"), vec![McpCapabilityKind::Resource])
+        );
+
+        assert!(toml::from_str::<McpMappingRules>(r#"exclude = [{ method_name = "debug_*" }]"#).is_err());
+        assert!(toml::from_str::<McpMappingRules>(r#"exclusions = [{ method = "debug_*" }]"#).is_err());
     }
 }
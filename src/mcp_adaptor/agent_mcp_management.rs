@@ -0,0 +1,130 @@
+use serde_json::{json, Value};
+
+use crate::golem::{AgentError, AgentId, AgentInvoker, AgentMethod, AgentMethodHints, AgentMethodTaskSupport, AgentRegistry, AgentType, DataSchema};
+use crate::mcp_adaptor::AgentMcpTool;
+
+// Built-in tools for the lifecycle of the agents of an agent type, rather than calling one of their methods.
+// Only there if enabled in the context, see `McpServerContext::with_management_tools`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AgentManagementOperation {
+    CreateAgent,
+    ListAgents,
+    GetAgentStatus,
+    DeleteAgent,
+}
+
+impl AgentManagementOperation {
+    pub const ALL: [AgentManagementOperation; 4] = [
+        AgentManagementOperation::CreateAgent,
+        AgentManagementOperation::ListAgents,
+        AgentManagementOperation::GetAgentStatus,
+        AgentManagementOperation::DeleteAgent,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            AgentManagementOperation::CreateAgent => "create_agent",
+            AgentManagementOperation::ListAgents => "list_agents",
+            AgentManagementOperation::GetAgentStatus => "get_agent_status",
+            AgentManagementOperation::DeleteAgent => "delete_agent",
+        }
+    }
+
+    // Described as a method with no parameters of its own, the agent is the `agent` argument in the global server
+    // and the server's own agent otherwise
+    fn get_method(&self, agent_type: &AgentType) -> AgentMethod {
+        let (title, description, hints) = match self {
+            AgentManagementOperation::CreateAgent => (
+                format!("Create {} agent", agent_type),
+                format!("Creates a {} agent, if it doesn't exist yet", agent_type),
+                get_hints(false, false, true),
+            ),
+            AgentManagementOperation::ListAgents => (
+                format!("List {} agents", agent_type),
+                format!("Lists the existing {} agents and their status", agent_type),
+                get_hints(true, false, true),
+            ),
+            AgentManagementOperation::GetAgentStatus => (
+                format!("Get {} agent status", agent_type),
+                format!("Returns the status of a {} agent", agent_type),
+                get_hints(true, false, true),
+            ),
+            AgentManagementOperation::DeleteAgent => (
+                format!("Delete {} agent", agent_type),
+                format!("Deletes a {} agent along with its state", agent_type),
+                get_hints(false, true, true),
+            ),
+        };
+
+        AgentMethod {
+            method_name: self.get_name().to_string(),
+            title: Some(title),
+            description: Some(description),
+            input_schema: vec![],
+            output_schema: vec![],
+            hints,
+            task_support: AgentMethodTaskSupport::Forbidden,
+            exposed_as: vec![],
+        }
+    }
+
+    // `agent_id` is None only for `list_agents`
+    pub async fn run(
+        &self,
+        invoker: &dyn AgentInvoker,
+        registry: &AgentRegistry,
+        agent_type: &AgentType,
+        agent_id: Option<AgentId>,
+    ) -> Result<Value, AgentError> {
+        let get_agent_id = || {
+            agent_id.clone().ok_or_else(|| AgentError::InvalidArguments {
+                message: format!("{} needs an agent", self.get_name()),
+            })
+        };
+
+        match self {
+            AgentManagementOperation::CreateAgent => {
+                let agent_info = invoker.create_agent(&get_agent_id()?).await?;
+                registry.add_agent(&agent_info.agent_id);
+                Ok(json!(agent_info))
+            }
+            AgentManagementOperation::ListAgents => {
+                let agents = invoker.list_agents(agent_type).await?;
+                Ok(json!({ "agents": agents }))
+            }
+            AgentManagementOperation::GetAgentStatus => Ok(json!(invoker.get_agent_status(&get_agent_id()?).await?)),
+            AgentManagementOperation::DeleteAgent => {
+                let agent_id = get_agent_id()?;
+                invoker.delete_agent(&agent_id).await?;
+                registry.remove_agent(&agent_id);
+                Ok(json!({ "deleted": agent_id }))
+            }
+        }
+    }
+}
+
+// The management tools of an agent type, with the constructor schema of the agent type if the agent is an argument
+pub fn get_management_tools(agent_type: &AgentType, constructor_schema: Option<DataSchema>) -> Vec<AgentMcpTool> {
+    AgentManagementOperation::ALL
+        .iter()
+        .map(|operation| AgentMcpTool {
+            agent_type: agent_type.clone(),
+            tool: operation.get_method(agent_type),
+            // Listing is for the agent type as a whole
+            constructor_schema: match operation {
+                AgentManagementOperation::ListAgents => None,
+                _ => constructor_schema.clone(),
+            },
+            management: Some(*operation),
+        })
+        .collect()
+}
+
+fn get_hints(read_only: bool, destructive: bool, idempotent: bool) -> AgentMethodHints {
+    AgentMethodHints {
+        read_only: Some(read_only),
+        destructive: Some(destructive),
+        idempotent: Some(idempotent),
+        open_world: Some(false),
+    }
+}
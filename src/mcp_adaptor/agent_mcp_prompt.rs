@@ -2,15 +2,42 @@ use futures::future::BoxFuture;
 use futures::FutureExt;
 use rmcp::ErrorData;
 use rmcp::handler::server::prompt::{GetPromptHandler, PromptContext};
-use rmcp::model::{GetPromptResult, PromptMessage, PromptMessageContent, PromptMessageRole};
-use crate::golem::AgentMethod;
+use rmcp::model::{GetPromptResult, Prompt, PromptArgument, PromptMessage, PromptMessageContent, PromptMessageRole};
+use crate::golem::{AgentMethod, AgentType};
 use crate::mcp_adaptor::{GolemAgentMcpServer};
 
 #[derive(Clone)]
 pub struct AgentMcpPrompt {
+    pub agent_type: AgentType,
     pub agent_method: AgentMethod,
 }
 
+impl AgentMcpPrompt {
+    // The name is up to the server, see `get_prompt_name`
+    pub fn get_prompt(&self, name: String) -> Prompt {
+        let arguments = self
+            .agent_method
+            .input_schema
+            .iter()
+            .map(|entry| PromptArgument {
+                name: entry.name.clone(),
+                title: None,
+                description: Some(format!("{}", entry.schema)),
+                required: Some(true),
+            })
+            .collect::<Vec<_>>();
+
+        Prompt {
+            name,
+            title: self.agent_method.title.clone(),
+            description: self.agent_method.description.clone(),
+            arguments: (!arguments.is_empty()).then_some(arguments),
+            icons: None,
+            meta: None,
+        }
+    }
+}
+
 impl GetPromptHandler<GolemAgentMcpServer, ()> for AgentMcpPrompt {
     fn handle(self, context: PromptContext<'_, GolemAgentMcpServer>) -> BoxFuture<'_, Result<GetPromptResult, ErrorData>> {
         async move {
@@ -1,4 +1,8 @@
-use crate::golem::AgentMethod;
+use rmcp::model::{AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceTemplate};
+
+use crate::golem::{AgentId, AgentMethod};
+
+const AGENT_RESOURCE_URI_PREFIX: &str = "golem://agents/";
 
 #[derive(Clone)]
 pub struct AgentMcpResource {
@@ -6,3 +10,43 @@ pub struct AgentMcpResource {
 }
 
 // Handlers and mapper instances to go  in here
+
+// Resource of an agent instance is `golem://agents/{agent_id}/{method_name}`
+impl AgentMcpResource {
+    pub fn get_uri(&self, agent_id: &AgentId) -> String {
+        format!("{}{}/{}", AGENT_RESOURCE_URI_PREFIX, agent_id, self.resource.method_name)
+    }
+
+    pub fn get_resource(&self, agent_id: &AgentId) -> Resource {
+        let mut resource = RawResource::new(self.get_uri(agent_id), self.resource.method_name.clone());
+        resource.title = self.resource.title.clone();
+        resource.description = self.resource.description.clone();
+        resource.mime_type = Some("application/json".to_string());
+        resource.no_annotation()
+    }
+
+    // For the global server, where there is no agent-id until the client picks one
+    pub fn get_resource_template(&self) -> ResourceTemplate {
+        RawResourceTemplate {
+            uri_template: format!("{}{{agent_id}}/{}", AGENT_RESOURCE_URI_PREFIX, self.resource.method_name),
+            name: self.resource.method_name.clone(),
+            title: self.resource.title.clone(),
+            description: self.resource.description.clone(),
+            mime_type: Some("application/json".to_string()),
+            icons: None,
+        }
+        .no_annotation()
+    }
+}
+
+// Returns the agent-id and the method name
+pub fn parse_agent_resource_uri(uri: &str) -> Option<(AgentId, String)> {
+    let path = uri.strip_prefix(AGENT_RESOURCE_URI_PREFIX)?;
+    let (agent_id, method_name) = path.rsplit_once('/')?;
+
+    if agent_id.is_empty() || method_name.is_empty() {
+        return None;
+    }
+
+    Some((agent_id.to_string(), method_name.to_string()))
+}
@@ -1,37 +1,102 @@
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use poem::http;
 use rmcp::{
-    handler::server::router::tool::ToolRouter, model::*, service::RequestContext, task_handler,
-    task_manager::OperationProcessor, tool_handler, ErrorData as McpError, RoleServer,
+    handler::server::router::tool::ToolRouter, model::*, service::RequestContext,
+    task_manager::{OperationDescriptor, OperationMessage, OperationResultTransport, ToolCallTaskResult},
+    ErrorData as McpError, Peer, RoleServer,
     ServerHandler,
 };
+use rmcp::handler::server::prompt::PromptContext;
 use rmcp::handler::server::router::prompt::PromptRouter;
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::service::NotificationContext;
 use serde_json::{json};
-use tokio::sync::{Mutex};
+use tokio::sync::broadcast;
+use tracing::Instrument;
+use tokio_util::sync::{CancellationToken, DropGuard};
 
-use crate::golem::{AgentId, AgentMethod, AgentType, ElementSchema};
-use crate::mcp_adaptor::{AgentMcpTool, HttpMeta, McpAgentCapability, McpToolSchema, McpToolSchemaMapper};
+use crate::golem::{get_agent_type, AgentEvent, AgentId, AgentInvocation, AgentRegistryEvent, ProgressReporter};
+use crate::mcp_adaptor::{get_completion, get_agent_tools, get_error_data, get_invocation_span, get_trace_context, write_audit_record, McpCallOutcome, PendingAuditRecord, get_named_prompts, get_named_tools, get_agent_prompts, get_session_id, McpTaskOutcome, McpTaskOwner, McpTaskRecord, parse_agent_resource_uri, end_session_with_service, wait_for_session_end, AgentMcpResource, AgentMcpTool, McpAgentCapability, McpElicitor, McpLoggerId, McpSampler, McpServerContext, McpSession, McpToolSchema, McpToolSchemaMapper, NotificationTarget};
 use crate::mcp_adaptor::agent_mcp_prompt::AgentMcpPrompt;
 
+pub const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V_2025_06_18;
+
+// Anything older than `PROTOCOL_VERSION` is negotiated down to, see `get_protocol_version`
+pub const SUPPORTED_PROTOCOL_VERSIONS: [ProtocolVersion; 3] = [
+    ProtocolVersion::V_2024_11_05,
+    ProtocolVersion::V_2025_03_26,
+    ProtocolVersion::V_2025_06_18,
+];
+
+// How long a session waits for the rest of a registry change (a reload is an event per agent type) before it
+// rebuilds its routers and tells the client
+pub const REGISTRY_EVENTS_DEBOUNCE: Duration = Duration::from_millis(50);
 
 #[derive(Clone)]
 pub struct GolemAgentMcpServer {
-    pub tool_router: ToolRouter<GolemAgentMcpServer>,
-    pub processor: Arc<Mutex<OperationProcessor>>,
+    pub agent_id: Option<AgentId>,
+    pub context: McpServerContext,
+    // Behind a lock as they are rebuilt when the agent type is redeployed
+    pub tool_router: Arc<RwLock<ToolRouter<GolemAgentMcpServer>>>,
+    pub prompt_router: Arc<RwLock<PromptRouter<GolemAgentMcpServer>>>,
+    // Resource uris this session subscribed to, they go away with the session
+    pub subscriptions: Arc<RwLock<HashSet<String>>>,
+    // Cancelled when the session's transport closes (see `end_session_with_service`),
+    // or when rmcp drops the server (all clones) before that
+    pub session_ct: CancellationToken,
+    // Cancelled requests waiting for the session to end, see `wait_for_session_end`
+    pub parked_requests: Arc<AtomicUsize>,
+    // Unlike the mcp session id, there is one for every transport
+    pub logger_id: McpLoggerId,
+    _session_guard: Arc<DropGuard>,
 }
 
 impl GolemAgentMcpServer {
-    // Supporting per agent-id or fully global with no agent information at all
-    pub fn new(agent_id: Option<AgentId>) -> Self {
+    // Supporting per agent-id or fully global with no agent information at all.
+    // `context` is shared between all server instances so that notifications can be pushed from outside a request
+    pub fn new(agent_id: Option<AgentId>, context: McpServerContext) -> Self {
+        let session_ct = CancellationToken::new();
+
         Self {
-            tool_router: Self::tool_router(agent_id),
-            processor: Arc::new(Mutex::new(OperationProcessor::new())),
+            agent_id: agent_id.clone(),
+            tool_router: Arc::new(RwLock::new(Self::tool_router(agent_id.clone(), &context))),
+            prompt_router: Arc::new(RwLock::new(Self::prompt_router(agent_id, &context))),
+            subscriptions: Arc::new(RwLock::new(HashSet::new())),
+            context,
+            logger_id: uuid::Uuid::new_v4().to_string(),
+            _session_guard: Arc::new(session_ct.clone().drop_guard()),
+            session_ct,
+            parked_requests: Arc::new(AtomicUsize::new(0)),
         }
     }
 
-    fn tool_router(agent_id: Option<AgentId>) -> ToolRouter<GolemAgentMcpServer> {
-        let tool_handlers = get_agent_tool_and_handlers(agent_id);
+    // Never returns while the session lasts, unless it has too many parked already
+    pub async fn park_cancelled_request(&self) {
+        wait_for_session_end(&self.session_ct, &self.parked_requests, &self.context.metrics).await
+    }
+
+    // Events inside this span are forwarded to the session once it sets a log level, see `McpLoggingLayer`.
+    // The global server records the agent id once a request says which agent it is for
+    fn request_span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "mcp_request",
+            mcp_logger = %self.logger_id,
+            agent_id = self.agent_id.as_ref().map(|agent_id| self.context.registry.redact_agent_id(agent_id)),
+        )
+    }
+
+    // Push a notification to a single session, to every session of an agent, or to every session,
+    // returning the number of sessions it was delivered to
+    pub async fn notify(&self, target: NotificationTarget, notification: ServerNotification) -> usize {
+        self.context.sessions.notify(target, notification).await
+    }
+
+    fn tool_router(agent_id: Option<AgentId>, context: &McpServerContext) -> ToolRouter<GolemAgentMcpServer> {
+        let tool_handlers = get_agent_tool_and_handlers(agent_id, context);
 
         let mut router = ToolRouter::<Self>::new();
 
@@ -42,8 +107,8 @@ impl GolemAgentMcpServer {
         router
     }
 
-    fn prompt_router(agent_id: Option<AgentId>) -> PromptRouter<GolemAgentMcpServer> {
-        let prompt_handlers = get_agent_prompt_and_handlers(agent_id);
+    fn prompt_router(agent_id: Option<AgentId>, context: &McpServerContext) -> PromptRouter<GolemAgentMcpServer> {
+        let prompt_handlers = get_agent_prompt_and_handlers(agent_id, context);
 
         let mut router = PromptRouter::<Self>::new();
 
@@ -53,126 +118,653 @@ impl GolemAgentMcpServer {
 
         router
     }
-}
-
-pub fn get_agent_prompt_and_handlers(agent_id: Option<AgentId>) -> Vec<(Prompt, AgentMcpPrompt)> {
-    // similar to get_agent_tool_and_handlers, but for prompts
-    // prompt name is `get_${method_name}_prompt`
-    vec![]
-}
-
-pub fn get_agent_tool_and_handlers(agent_id: Option<AgentId>) -> Vec<(Tool, AgentMcpTool)> {
 
-    match agent_id {
-        Some(agent) => {
-            // just dummy,
-            let agent_method = get_agent_methods(&agent);
-
-            let mut tools = vec![];
-
-            for method in agent_method.into_iter() {
-                let agent_method_mcp = McpAgentCapability::from(method);
-
-                match agent_method_mcp {
-                    McpAgentCapability::Tool(agent_mcp_tool) => {
-                        let McpToolSchema {input_schema, output_schema} = agent_mcp_tool.get_schema();
-                        let tool = Tool {
-                            name: Cow::from(agent_mcp_tool.tool.method_name.clone()),
-                            title: None,
-                            description: Some("An increment method that takes a number and increment it".into()),
-                            input_schema: Arc::new(input_schema),
-                            output_schema: output_schema.map(Arc::new),
-                            annotations: None,
-                            execution: None,
-                            icons: None,
-                            meta: None,
-                        };
-
-
-                        tools.push((tool, agent_mcp_tool));
-                    }
-                    McpAgentCapability::Resource(_) => {}
+    // The task store is the source of truth, the processor only needs to know what's still running.
+    // Operations that didn't get to record their own outcome (timed out) are failed here
+    async fn reap_operations(&self) {
+        let failed = {
+            let mut processor = self.context.processor.lock().await;
+            processor.check_timeouts();
+
+            let finished = processor
+                .peek_completed()
+                .iter()
+                .map(|result| result.descriptor.operation_id.clone())
+                .collect::<Vec<_>>();
+
+            finished
+                .iter()
+                .filter_map(|task_id| processor.take_completed_result(task_id))
+                .filter_map(|result| result.result.err().map(|error| (result.descriptor.operation_id, error.to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        for (task_id, error) in failed {
+            if let Ok(Some(mut record)) = self.context.tasks.get(&task_id).await {
+                if !record.is_terminal() {
+                    record.finish(McpTaskOutcome::Failed(McpError::internal_error(error, None)));
+                    let _ = self.context.tasks.put(record).await;
                 }
             }
+        }
+    }
+
+    // A task of another agent, or of someone else, is as good as not found
+    async fn get_task(&self, task_id: &str, context: &RequestContext<RoleServer>) -> Result<McpTaskRecord, McpError> {
+        self.reap_operations().await;
+
+        let owner = McpTaskOwner::from(&context.extensions);
+
+        let record = self
+            .context
+            .tasks
+            .get(task_id)
+            .await
+            .map_err(|error| McpError::internal_error(error, None))?
+            .filter(|record| self.agent_id.is_none() || record.agent_id == self.agent_id)
+            .filter(|record| record.owner == owner);
+
+        record.ok_or_else(|| McpError::resource_not_found(format!("task not found: {}", task_id), None))
+    }
+
+    // The agent resource behind the uri, provided it is exposed by this server
+    fn get_agent_resource(&self, uri: &str) -> Option<(AgentId, AgentMcpResource)> {
+        let (agent_id, method_name) = parse_agent_resource_uri(uri)?;
+
+        if self.agent_id.as_ref().is_some_and(|id| id != &agent_id) {
+            return None;
+        }
 
-            tools
-        },
-        None => {
-            let agent_method = get_agent_methods(&"any_agent".to_string());
-
-            let mut tools = vec![];
-
-            for method in agent_method.into_iter() {
-                let agent_method_mcp = McpAgentCapability::from(method);
-
-                match agent_method_mcp {
-                    McpAgentCapability::Tool(agent_mcp_tool) => {
-                        let McpToolSchema {input_schema, output_schema} = agent_mcp_tool.get_schema();
-                        let tool = Tool {
-                            name: Cow::from(agent_mcp_tool.tool.method_name.clone()),
-                            title: None,
-                            description: Some("An increment method that takes a number and increment it".into()),
-                            input_schema: Arc::new(input_schema),
-                            output_schema: output_schema.map(Arc::new),
-                            annotations: None,
-                            execution: None,
-                            icons: None,
-                            meta: None,
-                        };
-
-
-                        tools.push((tool, agent_mcp_tool));
+        get_agent_resources(Some(agent_id.clone()), &self.context)
+            .into_iter()
+            .find(|resource| resource.resource.method_name == method_name)
+            .map(|resource| (agent_id, resource))
+    }
+
+    async fn read_agent_resource(
+        &self,
+        uri: String,
+        agent_id: AgentId,
+        resource: AgentMcpResource,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let recorded_agent_id = self.context.registry.redact_agent_id(&agent_id);
+        if self.agent_id.is_none() {
+            tracing::Span::current().record("agent_id", &recorded_agent_id);
+        }
+
+        let invocation_span = get_invocation_span(&recorded_agent_id, &resource.resource.method_name);
+        let audit_record = PendingAuditRecord::start(
+            &context,
+            Some(recorded_agent_id),
+            &resource.resource.method_name,
+            &resource.resource.input_schema,
+            &JsonObject::new(),
+        );
+
+        let invocation = AgentInvocation {
+            agent_id,
+            method_name: resource.resource.method_name,
+            parameters: JsonObject::new(),
+            progress: ProgressReporter::default(),
+            elicitation: McpElicitor::requester(context.peer.clone()),
+            sampling: McpSampler::requester(context.peer.clone()),
+            cancellation: context.ct.clone(),
+            trace_context: get_trace_context(&invocation_span, &context),
+        };
+
+        let result = self
+            .context
+            .invocations
+            .invoke(self.context.invoker.as_ref(), invocation)
+            .instrument(invocation_span)
+            .await;
+
+        let outcome = match &result {
+            Some(Ok(_)) => McpCallOutcome::Ok,
+            Some(Err(_)) => McpCallOutcome::Error,
+            None => McpCallOutcome::Cancelled,
+        };
+        write_audit_record(self.context.audit.as_ref(), audit_record.finish(outcome)).await;
+
+        let Some(result) = result else {
+            self.park_cancelled_request().await;
+            return Err(McpError::internal_error("resource read cancelled", None));
+        };
+
+        let value = result.map_err(|error| get_error_data(&error))?;
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri,
+                mime_type: Some("application/json".to_string()),
+                text: value.to_string(),
+                meta: None,
+            }],
+        })
+    }
+
+    // Lives as long as the session. Keeps the routers in sync with the registry,
+    // lets the client know its lists are stale, and forwards state changes of subscribed resources
+    fn spawn_session_task(&self, session: McpSession) {
+        let agent_id = self.agent_id.clone();
+        let context = self.context.clone();
+        let tool_router = self.tool_router.clone();
+        let prompt_router = self.prompt_router.clone();
+        let subscriptions = self.subscriptions.clone();
+        let session_ct = self.session_ct.clone();
+        let mut events = context.registry.subscribe();
+        let mut agent_events = context.agent_events.subscribe();
+        let recorded_agent_id = session.agent_id.as_ref().map(|agent_id| context.registry.redact_agent_id(agent_id));
+        let span = tracing::info_span!("mcp_session", session_id = %session.session_id, agent_id = recorded_agent_id);
+        let mut lists = McpLists::get(&agent_id, &tool_router, &prompt_router, &context);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = session_ct.cancelled() => break,
+                    event = agent_events.recv() => match event {
+                        Ok(AgentEvent::StateChanged(changed_agent_id)) => {
+                            let updated = subscriptions
+                                .read()
+                                .unwrap()
+                                .iter()
+                                .filter(|uri| parse_agent_resource_uri(uri).is_some_and(|(agent_id, _)| agent_id == changed_agent_id))
+                                .cloned()
+                                .collect::<Vec<_>>();
+
+                            for uri in updated {
+                                if let Err(error) = session.peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri }).await {
+                                    tracing::warn!(session_id = %session.session_id, %error, "failed to send resources/updated notification");
+                                }
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!(session_id = %session.session_id, skipped, "missed agent state change events");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    event = events.recv() => match event {
+                        Ok(event) if !is_affected_by(&agent_id, &event) => {}
+                        // Missed a few events, rebuilding is idempotent so treat it as a change
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                            // A reload sends an event per agent type, they are taken in one go
+                            tokio::time::sleep(REGISTRY_EVENTS_DEBOUNCE).await;
+                            while let Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) = events.try_recv() {}
+
+                            *tool_router.write().unwrap() = Self::tool_router(agent_id.clone(), &context);
+                            *prompt_router.write().unwrap() = Self::prompt_router(agent_id.clone(), &context);
+
+                            let changed = McpLists::get(&agent_id, &tool_router, &prompt_router, &context);
+                            if let Err(error) = notify_list_changed(&session.peer, &lists, &changed).await {
+                                tracing::warn!(session_id = %session.session_id, %error, "failed to send list_changed notifications");
+                            }
+                            lists = changed;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
-                    McpAgentCapability::Resource(_) => {}
                 }
             }
 
-            tools
+            context.sessions.remove(&session.session_id).await;
+            context.metrics.session_closed(&session.agent_id);
+            tracing::info!(session_id = %session.session_id, "mcp session closed");
         }
+        .instrument(span));
     }
+}
 
+fn is_affected_by(agent_id: &Option<AgentId>, event: &AgentRegistryEvent) -> bool {
+    match agent_id {
+        Some(agent_id) => &get_agent_type(agent_id) == event.agent_type(),
+        None => true,
+    }
 }
 
+// What the session lists, so that only the lists a registry change touched are reported stale
+#[derive(PartialEq)]
+struct McpLists {
+    tools: Vec<Tool>,
+    prompts: Vec<Prompt>,
+    resources: Vec<Resource>,
+    resource_templates: Vec<ResourceTemplate>,
+}
 
-pub fn get_agent_methods(_agent_id: &AgentType) -> Vec<AgentMethod> {
-    vec![
-        AgentMethod {
-            method_name: "increment".into(),
-            input_schema: vec![
-                ("number".into(), ElementSchema::U32),
-            ],
-            output_schema: vec![("result".into(), ElementSchema::U32)],
+impl McpLists {
+    fn get(
+        agent_id: &Option<AgentId>,
+        tool_router: &RwLock<ToolRouter<GolemAgentMcpServer>>,
+        prompt_router: &RwLock<PromptRouter<GolemAgentMcpServer>>,
+        context: &McpServerContext,
+    ) -> Self {
+        Self {
+            tools: tool_router.read().unwrap().list_all(),
+            prompts: prompt_router.read().unwrap().list_all(),
+            resources: get_resources(agent_id, context),
+            resource_templates: get_resource_templates(agent_id, context),
         }
-    ]
+    }
+}
+
+async fn notify_list_changed(peer: &Peer<RoleServer>, before: &McpLists, after: &McpLists) -> Result<(), rmcp::ServiceError> {
+    if before.tools != after.tools {
+        peer.notify_tool_list_changed().await?;
+    }
+
+    if before.prompts != after.prompts {
+        peer.notify_prompt_list_changed().await?;
+    }
+
+    if before.resources != after.resources || before.resource_templates != after.resource_templates {
+        peer.notify_resource_list_changed().await?;
+    }
+
+    Ok(())
+}
+
+// The global server has no agent-id to form a uri with, it exposes templates instead
+fn get_resources(agent_id: &Option<AgentId>, context: &McpServerContext) -> Vec<Resource> {
+    match agent_id {
+        Some(agent_id) => get_agent_resources(Some(agent_id.clone()), context)
+            .iter()
+            .map(|resource| resource.get_resource(agent_id))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn get_resource_templates(agent_id: &Option<AgentId>, context: &McpServerContext) -> Vec<ResourceTemplate> {
+    match agent_id {
+        Some(_) => vec![],
+        None => get_agent_resources(None, context)
+            .iter()
+            .map(|resource| resource.get_resource_template())
+            .collect(),
+    }
+}
+
+// Like tools, colliding prompts are left out
+pub fn get_agent_prompt_and_handlers(agent_id: Option<AgentId>, context: &McpServerContext) -> Vec<(Prompt, AgentMcpPrompt)> {
+    let namespaced = agent_id.is_none();
+    let (named_prompts, errors) = get_named_prompts(get_agent_prompts(agent_id, context), namespaced);
+
+    for error in errors {
+        tracing::error!(%error, "prompt left out");
+    }
+
+    named_prompts
+        .into_iter()
+        .map(|(prompt_name, agent_mcp_prompt)| (agent_mcp_prompt.get_prompt(prompt_name), agent_mcp_prompt))
+        .collect()
+}
+
+pub fn get_agent_resources(agent_id: Option<AgentId>, context: &McpServerContext) -> Vec<AgentMcpResource> {
+    get_agent_capabilities(agent_id, context)
+        .into_iter()
+        .filter_map(|capability| match capability {
+            McpAgentCapability::Resource(agent_mcp_resource) => Some(agent_mcp_resource),
+            McpAgentCapability::Tool(_) | McpAgentCapability::Prompt(_) => None,
+        })
+        .collect()
+}
+
+// Colliding tools are left out of the router rather than failing the (already running) server,
+// startup is where `check_tool_names` catches them
+pub fn get_agent_tool_and_handlers(agent_id: Option<AgentId>, context: &McpServerContext) -> Vec<(Tool, AgentMcpTool)> {
+    let namespaced = agent_id.is_none();
+    let (named_tools, errors) = get_named_tools(get_agent_tools(agent_id, context), namespaced);
+
+    for error in errors {
+        tracing::error!(%error, "tool left out");
+    }
+
+    let mut tools = vec![];
+
+    for (tool_name, agent_mcp_tool) in named_tools {
+        let McpToolSchema {input_schema, output_schema} = agent_mcp_tool.get_schema();
+        let tool = Tool {
+            name: Cow::from(tool_name),
+            title: agent_mcp_tool.tool.title.clone(),
+            description: agent_mcp_tool.tool.description.clone().map(Cow::from),
+            input_schema: Arc::new(input_schema),
+            output_schema: output_schema.map(Arc::new),
+            annotations: Some(agent_mcp_tool.get_annotations()),
+            execution: agent_mcp_tool.get_execution(),
+            icons: None,
+            meta: None,
+        };
+
+        tools.push((tool, agent_mcp_tool));
+    }
+
+    tools
+}
+
+// Per agent-id we expose the methods of its agent type, and the global server exposes every agent type,
+// as mapped by the mapping rules of the context
+pub fn get_agent_capabilities(agent_id: Option<AgentId>, context: &McpServerContext) -> Vec<McpAgentCapability> {
+    let agent_type = agent_id.map(|agent_id| get_agent_type(&agent_id));
+
+    // From a single snapshot, so that a catalog being replaced (see `AgentRegistry::replace_all`) is seen whole or not at all
+    context
+        .registry
+        .get_agent_type_definitions()
+        .into_iter()
+        .filter(|(other, _)| agent_type.as_ref().is_none_or(|agent_type| agent_type == other))
+        .flat_map(|(agent_type, definition)| {
+            definition
+                .methods
+                .into_iter()
+                .flat_map(move |method| McpAgentCapability::from(&agent_type, method, &context.mapping_rules))
+        })
+        .collect()
 }
 
 // Almost all macros in rmcp was useless for us (and that's expected - and we are not using it for these helpers anyway).
-// We avoided it for the most part except for the below one, and that should also disappear.
-// It works for now. However, we should copy the relevant implenetations such as list-tools
-// and then avoid design flaws such as expecting listing to be pre-computed in GolemAgentMcpServer rather
-// than mcp-initialize phase deciding it. This will make the code far better in architecture and MCP lifecycle
-// although obviously verbose
-#[tool_handler(meta = Meta(rmcp::object!({"tool_meta_key": "tool_meta_value"})))]
-#[task_handler]
+// `tool_handler` assumed a router fixed at construction, whereas ours is rebuilt whenever the agent type
+// is redeployed, and `task_handler` kept task state in the (per session) server, which is lost on reconnect.
+// Hence both are implemented by hand below
 impl ServerHandler for GolemAgentMcpServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
+            protocol_version: PROTOCOL_VERSION,
             capabilities: ServerCapabilities::builder()
+                .enable_completions()
+                .enable_logging()
                 .enable_prompts()
+                .enable_prompts_list_changed()
                 .enable_resources()
+                .enable_resources_list_changed()
+                .enable_resources_subscribe()
                 .enable_tools()
+                .enable_tool_list_changed()
+                .enable_tasks_with(TasksCapability {
+                    requests: Some(TaskRequestsCapability {
+                        tools: Some(ToolsTaskCapability {
+                            call: Some(JsonObject::new()),
+                        }),
+                        ..Default::default()
+                    }),
+                    list: Some(JsonObject::new()),
+                    cancel: Some(JsonObject::new()),
+                })
                 .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some("This server provides  tools related to agent in golem and prompts. Tools: increment, decrement, get_value, say_hello, echo, sum. Prompts: example_prompt (takes a message), counter_analysis (analyzes counter state with a goal).".to_string()),
         }
     }
 
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // Cloned out so that the lock isn't held across the agent invocation
+        let tool_router = self.tool_router.read().unwrap().clone();
+        let tcc = ToolCallContext::new(self, request, context);
+        tool_router.call(tcc).instrument(self.request_span()).await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult {
+            tools: self.tool_router.read().unwrap().list_all(),
+            meta: Some(Meta(rmcp::object!({"tool_meta_key": "tool_meta_value"}))),
+            next_cursor: None,
+        })
+    }
+
+    fn get_tool(&self, name: &str) -> Option<Tool> {
+        self.tool_router.read().unwrap().get(name).cloned()
+    }
+
+    // Only tools with task support get here (rmcp rejects the rest), see `AgentMcpTool::get_execution`
+    async fn enqueue_task(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CreateTaskResult, McpError> {
+        self.reap_operations().await;
+
+        // Request ids are only unique within a session, task ids have to be unique across sessions
+        let task_id = uuid::Uuid::new_v4().to_string();
+        let record = McpTaskRecord::new(
+            task_id.clone(),
+            self.agent_id.clone(),
+            McpTaskOwner::from(&context.extensions),
+            request.name.to_string(),
+            self.context.task_ttl,
+        );
+
+        self.context
+            .tasks
+            .put(record.clone())
+            .await
+            .map_err(|error| McpError::internal_error(error, None))?;
+
+        // rmcp cancels the request's token as soon as the task handle is sent back,
+        // so the task gets its own, which tasks/cancel cancels
+        let mut task_context = context.clone();
+        task_context.ct = tokio_util::sync::CancellationToken::new();
+
+        let descriptor = OperationDescriptor::new(task_id.clone(), request.name.to_string())
+            .with_context(task_context.clone());
+
+        let server = self.clone();
+        let task = record.task.clone();
+        let mut task_request = request;
+        task_request.task = None;
+
+        let future = Box::pin(async move {
+            let result = server.call_tool(task_request, task_context).await;
+
+            let mut record = record;
+            record.finish(match &result {
+                Ok(call_tool_result) => McpTaskOutcome::Completed(call_tool_result.clone()),
+                Err(error) => McpTaskOutcome::Failed(error.clone()),
+            });
+
+            if let Err(error) = server.context.tasks.put(record).await {
+                tracing::error!(%task_id, %error, "failed to store task result");
+            }
+
+            Ok(Box::new(ToolCallTaskResult::new(task_id, result)) as Box<dyn OperationResultTransport>)
+        }
+        // Outlives the request, but is still part of it
+        .instrument(tracing::Span::current()));
+
+        self.context
+            .processor
+            .lock()
+            .await
+            .submit_operation(OperationMessage::new(descriptor, future))
+            .map_err(|error| McpError::internal_error(format!("failed to enqueue task: {error}"), None))?;
+
+        Ok(CreateTaskResult { task })
+    }
+
+    async fn list_tasks(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListTasksResult, McpError> {
+        self.reap_operations().await;
+
+        // Without a principal or a session, a task is only known to whoever has its id
+        let Some(owner) = McpTaskOwner::from(&context.extensions) else {
+            return Ok(ListTasksResult {
+                total: Some(0),
+                tasks: vec![],
+                next_cursor: None,
+            });
+        };
+
+        let tasks = self
+            .context
+            .tasks
+            .list(self.agent_id.as_ref(), &owner)
+            .await
+            .map_err(|error| McpError::internal_error(error, None))?
+            .into_iter()
+            .map(|record| record.task)
+            .collect::<Vec<_>>();
+
+        Ok(ListTasksResult {
+            total: Some(tasks.len() as u64),
+            tasks,
+            next_cursor: None,
+        })
+    }
+
+    async fn get_task_info(
+        &self,
+        request: GetTaskInfoParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetTaskResult, McpError> {
+        let record = self.get_task(&request.task_id, &context).await?;
+
+        Ok(GetTaskResult {
+            meta: None,
+            task: record.task,
+        })
+    }
+
+    // Blocks until the task reaches a terminal state
+    async fn get_task_result(
+        &self,
+        request: GetTaskResultParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetTaskPayloadResult, McpError> {
+        loop {
+            let record = self.get_task(&request.task_id, &context).await?;
+
+            match (record.task.status, record.outcome) {
+                (_, Some(McpTaskOutcome::Completed(result))) => {
+                    let value = serde_json::to_value(result)
+                        .map_err(|error| McpError::internal_error(error.to_string(), None))?;
+                    return Ok(GetTaskPayloadResult(value));
+                }
+                (_, Some(McpTaskOutcome::Failed(error))) => return Err(error),
+                (TaskStatus::Cancelled, None) => {
+                    return Err(McpError::invalid_request(format!("task cancelled: {}", request.task_id), None));
+                }
+                _ => {}
+            }
+
+            tokio::select! {
+                _ = context.ct.cancelled() => {
+                    self.park_cancelled_request().await;
+                    return Err(McpError::internal_error("tasks/result cancelled", None));
+                }
+                _ = tokio::time::sleep(std::time::Duration::from_millis(100)) => {}
+            }
+        }
+    }
+
+    async fn cancel_task(
+        &self,
+        request: CancelTaskParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CancelTaskResult, McpError> {
+        let mut record = self.get_task(&request.task_id, &context).await?;
+
+        if record.is_terminal() {
+            return Err(McpError::invalid_params(
+                format!("task already finished: {}", request.task_id),
+                None,
+            ));
+        }
+
+        let aborted = {
+            let mut processor = self.context.processor.lock().await;
+
+            // Signal the backend first, aborting only drops the invocation future
+            if let Some(ct) = processor
+                .task_descriptor(&request.task_id)
+                .and_then(|descriptor| descriptor.context.as_ref())
+                .map(|context| context.ct.clone())
+            {
+                ct.cancel();
+            }
+
+            processor.cancel_task(&request.task_id)
+        };
+
+        // Not running (anymore), it may have finished in the meantime
+        if !aborted {
+            record = self.get_task(&request.task_id, &context).await?;
+
+            if record.is_terminal() {
+                return Err(McpError::invalid_params(
+                    format!("task already finished: {}", request.task_id),
+                    None,
+                ));
+            }
+        }
+
+        record.cancel();
+
+        self.context
+            .tasks
+            .put(record.clone())
+            .await
+            .map_err(|error| McpError::internal_error(error, None))?;
+
+        Ok(CancelTaskResult {
+            meta: None,
+            task: record.task,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let prompt_router = self.prompt_router.read().unwrap().clone();
+        let prompt_context = PromptContext::new(self, request.name, request.arguments, context);
+        prompt_router.get_prompt(prompt_context).instrument(self.request_span()).await
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult {
+            prompts: self.prompt_router.read().unwrap().list_all(),
+            meta: None,
+            next_cursor: None,
+        })
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult {
+            resources: get_resources(&self.agent_id, &self.context),
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
     async fn read_resource(
         &self,
         ReadResourceRequestParams { meta: _, uri }: ReadResourceRequestParams,
-        _: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<ReadResourceResult, McpError> {
+        if let Some((agent_id, resource)) = self.get_agent_resource(&uri) {
+            return self
+                .read_agent_resource(uri, agent_id, resource, context)
+                .instrument(self.request_span())
+                .await;
+        }
+
         match uri.as_str() {
             "str:////Users/to/some/path/" => {
                 let cwd = "/Users/to/some/path/";
@@ -202,42 +794,124 @@ impl ServerHandler for GolemAgentMcpServer {
     ) -> Result<ListResourceTemplatesResult, McpError> {
         Ok(ListResourceTemplatesResult {
             next_cursor: None,
-            resource_templates: Vec::new(),
+            resource_templates: get_resource_templates(&self.agent_id, &self.context),
             meta: None,
         })
     }
 
+    async fn subscribe(
+        &self,
+        SubscribeRequestParams { meta: _, uri }: SubscribeRequestParams,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        // Only agent resources change, the rest are static
+        if self.get_agent_resource(&uri).is_none() {
+            return Err(McpError::resource_not_found(
+                "resource_not_found",
+                Some(json!({
+                    "uri": uri
+                })),
+            ));
+        }
+
+        self.subscriptions.write().unwrap().insert(uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        UnsubscribeRequestParams { meta: _, uri }: UnsubscribeRequestParams,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions.write().unwrap().remove(&uri);
+        Ok(())
+    }
+
+    async fn complete(
+        &self,
+        request: CompleteRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        let capabilities = get_agent_capabilities(self.agent_id.clone(), &self.context);
+        let completion = get_completion(&request, self.agent_id.as_ref(), capabilities, &self.context.registry);
+
+        Ok(CompleteResult { completion })
+    }
+
+    async fn set_level(
+        &self,
+        SetLevelRequestParams { meta: _, level }: SetLevelRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let first = self.context.loggers.set_level(&self.logger_id, context.peer, level);
+
+        if first {
+            let loggers = self.context.loggers.clone();
+            let logger_id = self.logger_id.clone();
+            let session_ct = self.session_ct.clone();
+
+            tokio::spawn(async move {
+                session_ct.cancelled().await;
+                loggers.remove(&logger_id);
+            });
+        }
+
+        Ok(())
+    }
+
     async fn initialize(
         &self,
         request: InitializeRequestParams,
         context: RequestContext<RoleServer>,
     ) -> Result<InitializeResult, McpError> {
-
-        dbg!(context.extensions.len());
-        dbg!(&request);
+        tracing::debug!(client = ?request.client_info, protocol_version = %request.protocol_version, "initialize");
 
         // Extract http::request::Parts (injected by rmcp's StreamableHttpService)
         if let Some(parts) = context.extensions.get::<http::request::Parts>() {
-            println!("\n================== MCP INITIALIZE ==================");
-            println!("Version      : {:?}", parts.version);
-            println!("Method       : {:?}", parts.method);
-            println!("URI          : {:?}", parts.uri);
-            println!("Path         : {:?}", parts.uri.path());
-            println!("Query        : {:?}", parts.uri.query());
-            println!("Headers      : {:#?}", parts.headers);
-            println!("=====================================================\n");
+            // Not logging credentials
+            let mut headers = parts.headers.clone();
+            headers.remove(http::header::AUTHORIZATION);
+
+            // The path of an agent's own endpoint has the agent id in it, which may hold sensitive params
+            let uri = self.agent_id.is_none().then(|| parts.uri.to_string());
 
             tracing::info!(
                 version = ?parts.version,
                 method = ?parts.method,
-                uri = %parts.uri,
-                headers = ?parts.headers,
+                uri,
+                headers = ?headers,
                 "initialize from http server"
             );
         } else {
-            println!("No http::request::Parts found in context.extensions");
+            tracing::debug!("no http::request::Parts in the initialize request, not an http transport");
         }
 
+        end_session_with_service(context.ct, self.session_ct.clone());
+
         Ok(self.get_info())
     }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        // Golem creates agents on first use, so an agent with a session is one worth suggesting
+        if let Some(agent_id) = &self.agent_id {
+            self.context.registry.add_agent(agent_id);
+        }
+
+        match get_session_id(&context.extensions) {
+            Some(session_id) => {
+                let session = McpSession {
+                    session_id,
+                    agent_id: self.agent_id.clone(),
+                    peer: context.peer,
+                };
+
+                self.context.sessions.register(session.clone()).await;
+                self.context.metrics.session_created(&session.agent_id);
+                self.spawn_session_task(session);
+            }
+            None => {
+                tracing::warn!("client initialized without a session id, server initiated notifications are disabled");
+            }
+        }
+    }
 }
\ No newline at end of file
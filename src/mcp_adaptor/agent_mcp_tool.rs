@@ -1,16 +1,105 @@
+use std::time::Instant;
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use rmcp::ErrorData;
 use rmcp::handler::server::tool::{CallToolHandler, ToolCallContext};
-use rmcp::model::{CallToolResult, JsonObject};
-use serde_json::json;
-use crate::golem::{AgentMethod, ElementSchema};
+use rmcp::model::{CallToolResult, JsonObject, TaskSupport, ToolAnnotations, ToolExecution};
+use serde_json::{json, Value};
+use tracing::Instrument;
+use crate::golem::{get_agent_id, validate_data, AgentError, AgentId, AgentInvocation, AgentMethod, AgentMethodTaskSupport, AgentType, DataSchema, ElementSchema};
+use crate::mcp_adaptor::agent_mcp_management::AgentManagementOperation;
 use crate::mcp_adaptor::agent_mcp_server::GolemAgentMcpServer;
+use crate::mcp_adaptor::mcp_elicitation::McpElicitor;
+use crate::mcp_adaptor::mcp_content::{get_protocol_version, get_tool_result};
+use crate::mcp_adaptor::mcp_errors::get_call_tool_result;
+use crate::mcp_adaptor::mcp_sampling::McpSampler;
+use crate::mcp_adaptor::mcp_progress::{McpProgress, PROGRESS_NOTIFICATION_INTERVAL};
 use crate::mcp_adaptor::mcp_schema::{McpToolSchema, McpToolSchemaMapper};
+use crate::mcp_adaptor::mcp_tracing::{get_invocation_span, get_trace_context};
+use crate::mcp_adaptor::mcp_audit::{write_audit_record, PendingAuditRecord};
+use crate::mcp_adaptor::mcp_metrics::McpCallOutcome;
+use crate::mcp_adaptor::http_meta::get_principal;
+use crate::mcp_adaptor::mcp_sessions::get_session_id;
+
+// The global server has no agent to call, so its tools take the constructor parameters of one as this argument
+pub const AGENT_ARGUMENT: &str = "agent";
 
 #[derive(Clone)]
 pub struct AgentMcpTool {
+    pub agent_type: AgentType,
     pub tool: AgentMethod,
+    // Only in the global server, where the agent is given as the `agent` argument
+    pub constructor_schema: Option<DataSchema>,
+    // Set for the built-in lifecycle tools, which don't invoke the agent
+    pub management: Option<AgentManagementOperation>,
+}
+
+impl AgentMcpTool {
+    pub fn with_agent_argument(mut self, constructor_schema: DataSchema) -> Self {
+        self.constructor_schema = Some(constructor_schema);
+        self
+    }
+
+    async fn call_management(
+        self,
+        operation: AgentManagementOperation,
+        server: &GolemAgentMcpServer,
+        mut parameters: JsonObject,
+    ) -> Result<CallToolResult, ErrorData> {
+        let agent_id = match operation {
+            AgentManagementOperation::ListAgents => None,
+            _ => match self.get_agent_id(server.agent_id.as_ref(), &mut parameters) {
+                Ok(agent_id) => Some(agent_id),
+                Err(message) => return get_call_tool_result(AgentError::InvalidArguments { message }),
+            },
+        };
+
+        let context = &server.context;
+
+        match operation.run(context.invoker.as_ref(), &context.registry, &self.agent_type, agent_id).await {
+            Ok(value) => Ok(CallToolResult::structured(value)),
+            Err(error) => get_call_tool_result(error),
+        }
+    }
+
+    // The agent of a per agent server, otherwise the one the `agent` argument points to (which is taken out of the parameters)
+    fn get_agent_id(&self, agent_id: Option<&AgentId>, parameters: &mut JsonObject) -> Result<AgentId, String> {
+        match (agent_id, &self.constructor_schema) {
+            (Some(agent_id), _) => Ok(agent_id.clone()),
+            (None, Some(constructor_schema)) => match parameters.remove(AGENT_ARGUMENT) {
+                Some(Value::Object(params)) => get_agent_id(&self.agent_type, constructor_schema, &params)
+                    .map_err(|error| format!("invalid `{}`: {}", AGENT_ARGUMENT, error)),
+                _ => Err(format!(
+                    "missing or invalid `{}`, expected the constructor parameters of a {} agent",
+                    AGENT_ARGUMENT, self.agent_type
+                )),
+            },
+            (None, None) => Err(format!("missing `{}`, there is no agent to call", AGENT_ARGUMENT)),
+        }
+    }
+
+    // Hints the agent author didn't give stay unset, so that clients fall back to the spec defaults
+    pub fn get_annotations(&self) -> ToolAnnotations {
+        let hints = &self.tool.hints;
+
+        ToolAnnotations {
+            title: self.tool.title.clone(),
+            read_only_hint: hints.read_only,
+            destructive_hint: hints.destructive,
+            idempotent_hint: hints.idempotent,
+            open_world_hint: hints.open_world,
+        }
+    }
+
+    pub fn get_execution(&self) -> Option<ToolExecution> {
+        let task_support = match self.tool.task_support {
+            AgentMethodTaskSupport::Forbidden => return None,
+            AgentMethodTaskSupport::Optional => TaskSupport::Optional,
+            AgentMethodTaskSupport::Required => TaskSupport::Required,
+        };
+
+        Some(ToolExecution::new().with_task_support(task_support))
+    }
 }
 
 // While `CallToolHandler` is auto implemented by `tool_handler` macro usually
@@ -23,12 +112,153 @@ impl CallToolHandler<GolemAgentMcpServer, ()> for AgentMcpTool {
         self,
         context: ToolCallContext<'_, GolemAgentMcpServer>,
     ) -> BoxFuture<'_, Result<CallToolResult, ErrorData>> {
-        let _arguments: Option<JsonObject> = context.arguments;
+        let server = context.service;
+        let tool_name = context.name.to_string();
+        let agent_type = self.agent_type.clone();
+        let method_name = self.tool.method_name.clone();
+        let start = Instant::now();
+
+        let arguments = context.arguments.clone().unwrap_or_default();
+        let agent_id = self.get_agent_id(server.agent_id.as_ref(), &mut arguments.clone()).ok();
+
+        let rate_limit_keys = server.context.rate_limits.get_tool_call_keys(
+            get_principal(&context.request_context.extensions).as_ref().map(|principal| principal.0.as_str()),
+            get_session_id(&context.request_context.extensions).as_ref(),
+            agent_id.as_ref(),
+            &tool_name,
+        );
+
+        let audit_record = PendingAuditRecord::start(
+            &context.request_context,
+            agent_id.as_ref().map(|agent_id| server.context.registry.redact_agent_id(agent_id)),
+            &self.tool.method_name,
+            &self.tool.input_schema,
+            &arguments,
+        );
+
+        // Checked before anything else, a client over its limit shouldn't get to keep the agent busy
+        let call = match server.context.rate_limiter.try_acquire(&rate_limit_keys) {
+            Ok(()) => Ok(self.call_tool(context)),
+            Err(exceeded) => Err(exceeded),
+        };
 
         async move {
-            Ok(CallToolResult::structured(
-                json!({"result": "example output"})
-            ))
+            let (result, outcome) = match call {
+                Ok(call) => match call.await {
+                    Some(result) => {
+                        let outcome = match &result {
+                            Ok(result) if result.is_error == Some(true) => McpCallOutcome::ToolError,
+                            Ok(_) => McpCallOutcome::Ok,
+                            Err(_) => McpCallOutcome::Error,
+                        };
+
+                        (Some(result), outcome)
+                    }
+                    None => (None, McpCallOutcome::Cancelled),
+                },
+                Err(exceeded) => {
+                    server.context.metrics.observe_rate_limited(exceeded.scope);
+
+                    let error = AgentError::RateLimited {
+                        message: format!("too many calls per {}", exceeded.scope.get_name()),
+                        retry_after_ms: (exceeded.retry_after.as_secs_f64() * 1000.0).ceil() as u64,
+                    };
+
+                    (Some(get_call_tool_result(error)), McpCallOutcome::RateLimited)
+                }
+            };
+
+            // Recorded before a cancelled call parks, which may be for as long as the session lasts
+            server.context.metrics.observe_tool_call(&tool_name, &agent_type, outcome, start.elapsed());
+            write_audit_record(server.context.audit.as_ref(), audit_record.finish(outcome)).await;
+
+            match result {
+                Some(result) => result,
+                None => {
+                    tracing::info!(%method_name, "tool call cancelled");
+                    server.park_cancelled_request().await;
+                    Err(ErrorData::internal_error("tool call cancelled", None))
+                }
+            }
+        }
+            .boxed()
+    }
+}
+
+impl AgentMcpTool {
+    // `None` if the call got cancelled, which is left to the caller to record and to not respond to
+    fn call_tool(
+        self,
+        context: ToolCallContext<'_, GolemAgentMcpServer>,
+    ) -> BoxFuture<'_, Option<Result<CallToolResult, ErrorData>>> {
+        let mut parameters: JsonObject = context.arguments.unwrap_or_default();
+        let server = context.service;
+
+        if let Some(operation) = self.management {
+            return self.call_management(operation, server, parameters).map(Some).boxed();
+        }
+
+        // Checked here rather than left to the backend, so that every backend reports it the same way
+        let agent_id = match self.get_agent_id(server.agent_id.as_ref(), &mut parameters) {
+            Ok(agent_id) => agent_id,
+            Err(message) => return async move { Some(get_call_tool_result(AgentError::InvalidArguments { message })) }.boxed(),
+        };
+
+        if let Err(message) = validate_data(&self.tool.input_schema, &parameters) {
+            return async move { Some(get_call_tool_result(AgentError::InvalidArguments { message })) }.boxed();
+        }
+
+        // The global server only knows the agent now
+        let recorded_agent_id = server.context.registry.redact_agent_id(&agent_id);
+        if server.agent_id.is_none() {
+            tracing::Span::current().record("agent_id", &recorded_agent_id);
+        }
+
+        let invocation_span = get_invocation_span(&recorded_agent_id, &self.tool.method_name);
+        let trace_context = get_trace_context(&invocation_span, &context.request_context);
+
+        let peer = context.request_context.peer.clone();
+        let cancellation = context.request_context.ct.clone();
+
+        // Progress is only reported if the client asked for it by sending a progress token
+        let progress = context
+            .request_context
+            .meta
+            .get_progress_token()
+            .map(|token| McpProgress::start(context.request_context.peer.clone(), token, PROGRESS_NOTIFICATION_INTERVAL));
+
+        async move {
+            // So that completions can suggest it from now on
+            if server.agent_id.is_none() {
+                server.context.registry.add_agent(&agent_id);
+            }
+
+            let invocation = AgentInvocation {
+                agent_id: agent_id.clone(),
+                method_name: self.tool.method_name.clone(),
+                parameters,
+                progress: progress.as_ref().map(McpProgress::reporter).unwrap_or_default(),
+                elicitation: McpElicitor::requester(peer.clone()),
+                sampling: McpSampler::requester(peer.clone()),
+                cancellation,
+                trace_context,
+            };
+
+            let result = server
+                .context
+                .invocations
+                .invoke(server.context.invoker.as_ref(), invocation)
+                .instrument(invocation_span)
+                .await;
+
+            if let Some(progress) = progress {
+                progress.finish().await;
+            }
+
+            match result? {
+                Ok(value) => Some(get_tool_result(&agent_id, &self.tool, value, &get_protocol_version(&peer))),
+                Err(error) => Some(get_call_tool_result(error)),
+            }
         }
             .boxed()
     }
@@ -38,18 +268,13 @@ impl CallToolHandler<GolemAgentMcpServer, ()> for AgentMcpTool {
 impl McpToolSchemaMapper for AgentMcpTool {
     fn get_schema(&self) -> McpToolSchema {
         let mut properties = serde_json::Map::new();
-        for (param_name, element_schema) in self.tool.input_schema.iter() {
-            // For simplicity, we treat element_schema as a string describing the type
-            // In a real implementation, this would be more complex and handle nested structures
-            let schema = match element_schema {
-                ElementSchema::String => json!({"type": "string"}), // We will be port this POC soon to Golem where the match on is ElementSchema I guess
-                ElementSchema::U32 => json!({"type": "integer"}),
-                ElementSchema::Bool => json!({"type": "boolean"}),
-                _ => json!({"type": "string"}), // Default to string for unknown types
-            };
-            properties.insert(param_name.clone(), schema);
+        for entry in self.tool.input_schema.iter() {
+            properties.insert(entry.name.clone(), get_json_schema(&entry.schema));
+        }
+        if let Some(constructor_schema) = &self.constructor_schema {
+            properties.insert(AGENT_ARGUMENT.to_string(), get_agent_argument_schema(&self.agent_type, constructor_schema));
         }
-        let input_schema: JsonObject = json!({
+        let mut input_schema: JsonObject = json!({
             "type": "object",
             "properties": properties,
         })
@@ -57,27 +282,30 @@ impl McpToolSchemaMapper for AgentMcpTool {
             .unwrap()
             .clone();
 
+        // Every parameter is checked by `validate_data`, none of them is optional
+        let mut required = self.tool.input_schema.iter().map(|entry| entry.name.clone()).collect::<Vec<_>>();
+        if self.constructor_schema.is_some() {
+            required.push(AGENT_ARGUMENT.to_string());
+        }
+        input_schema.insert("required".to_string(), json!(required));
+
 
         let mut properties = serde_json::Map::new();
 
-        if (self.tool.output_schema.is_empty()) {
+        // Unstructured outputs are returned as content items rather than in the structured content
+        for entry in self.tool.output_schema.iter() {
+            if !entry.schema.is_unstructured() {
+                properties.insert(entry.name.clone(), get_json_schema(&entry.schema));
+            }
+        }
+
+        if properties.is_empty() {
             return McpToolSchema {
                 input_schema,
                 output_schema: None,
             };
         }
 
-        for (param_name, element_schema) in self.tool.input_schema.iter() {
-            // For simplicity, we treat element_schema as a string describing the type
-            // In a real implementation, this would be more complex and handle nested structures
-            let schema = match element_schema {
-                ElementSchema::String => json!({"type": "string"}), // We will be port this POC soon to Golem where the match on is ElementSchema I guess
-                ElementSchema::U32 => json!({"type": "integer"}),
-                ElementSchema::Bool => json!({"type": "boolean"}),
-                _ => json!({"type": "string"}), // Default to string for unknown types
-            };
-            properties.insert(param_name.clone(), schema);
-        }
         let output_schema: JsonObject = json!({
            "type": "object",
            "properties": properties,
@@ -91,4 +319,70 @@ impl McpToolSchemaMapper for AgentMcpTool {
             output_schema: Some(output_schema),
         }
     }
-}
\ No newline at end of file
+}
+
+// The agent is created on first use, so any constructor parameters will do
+fn get_agent_argument_schema(agent_type: &AgentType, constructor_schema: &DataSchema) -> Value {
+    let mut properties = serde_json::Map::new();
+    for entry in constructor_schema.iter() {
+        properties.insert(entry.name.clone(), get_json_schema(&entry.schema));
+    }
+
+    json!({
+        "type": "object",
+        "description": format!("Constructor parameters of the {} agent, which also make up its id", agent_type),
+        "properties": properties,
+        "required": constructor_schema.iter().map(|entry| &entry.name).collect::<Vec<_>>(),
+    })
+}
+
+// For simplicity, we treat element_schema as a string describing the type
+// In a real implementation, this would be more complex and handle nested structures
+fn get_json_schema(element_schema: &ElementSchema) -> Value {
+    match element_schema {
+        ElementSchema::String => json!({"type": "string"}), // We will be port this POC soon to Golem where the match on is ElementSchema I guess
+        ElementSchema::U32 => json!({"type": "integer"}),
+        ElementSchema::Bool => json!({"type": "boolean"}),
+        ElementSchema::Enum(values) => json!({"type": "string", "enum": values}),
+        ElementSchema::UnstructuredText { mime_type } => match mime_type {
+            Some(mime_type) => json!({"type": "string", "contentMediaType": mime_type}),
+            None => json!({"type": "string"}),
+        },
+        ElementSchema::UnstructuredBinary { mime_type } => match mime_type {
+            Some(mime_type) => json!({"type": "string", "contentEncoding": "base64", "contentMediaType": mime_type}),
+            None => json!({"type": "string", "contentEncoding": "base64"}),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::golem::get_counter_agent_type;
+
+    use super::*;
+
+    fn tool(constructor_schema: Option<DataSchema>) -> AgentMcpTool {
+        let (agent_type, mut definition) = get_counter_agent_type();
+        AgentMcpTool {
+            agent_type,
+            tool: definition.methods.remove(0),
+            constructor_schema,
+            management: None,
+        }
+    }
+
+    #[test]
+    fn every_parameter_is_required() {
+        let schema = tool(None).get_schema();
+        assert_eq!(schema.input_schema["required"], json!(["number"]));
+    }
+
+    #[test]
+    fn global_tools_also_require_the_agent() {
+        let (_, definition) = get_counter_agent_type();
+        let schema = tool(Some(definition.constructor_schema)).get_schema();
+
+        assert_eq!(schema.input_schema["required"], json!(["number", AGENT_ARGUMENT]));
+        assert_eq!(schema.input_schema["properties"][AGENT_ARGUMENT]["required"], json!(["id"]));
+    }
+}
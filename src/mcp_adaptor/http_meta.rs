@@ -1,6 +1,20 @@
+use rmcp::model::Extensions;
+
 #[derive(Clone)]
 pub struct HttpMeta {
     pub uri: http::Uri,
     pub headers: http::HeaderMap,
     pub version: http::Version,
-}
\ No newline at end of file
+}
+
+// Who made the request, as a request extension set by whatever authenticated it. rmcp hands the http request parts
+// to the handlers, so it is there for anything running inside a session too
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AuthPrincipal(pub String);
+
+pub fn get_principal(extensions: &Extensions) -> Option<AuthPrincipal> {
+    extensions
+        .get::<http::request::Parts>()
+        .and_then(|parts| parts.extensions.get::<AuthPrincipal>())
+        .cloned()
+}
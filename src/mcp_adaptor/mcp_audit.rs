@@ -0,0 +1,123 @@
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rmcp::service::RequestContext;
+use rmcp::RoleServer;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::golem::{redact_data, AgentId, DataSchema};
+use crate::mcp_adaptor::{get_principal, get_session_id, McpCallOutcome, McpSessionId};
+
+// Who called which agent method with what, and how it went
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AuditRecord {
+    // When the call started
+    pub timestamp: DateTime<Utc>,
+    // Only there with authentication
+    pub principal: Option<String>,
+    pub session_id: Option<McpSessionId>,
+    // Missing if the call didn't get as far as saying which agent it is for
+    pub agent_id: Option<AgentId>,
+    pub method_name: String,
+    // As far as they can be recorded, see `redact_data`
+    pub arguments: Map<String, Value>,
+    pub status: McpCallOutcome,
+    pub duration_ms: u64,
+}
+
+// Where audit records go. A record is written before the client gets the result, a sink that fails to write
+// doesn't fail the call though
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, record: &AuditRecord) -> Result<(), String>;
+}
+
+// A call on its way, see `finish`
+pub struct PendingAuditRecord {
+    timestamp: DateTime<Utc>,
+    started: Instant,
+    principal: Option<String>,
+    session_id: Option<McpSessionId>,
+    agent_id: Option<AgentId>,
+    method_name: String,
+    arguments: Map<String, Value>,
+}
+
+impl PendingAuditRecord {
+    pub fn start(
+        context: &RequestContext<RoleServer>,
+        agent_id: Option<AgentId>,
+        method_name: &str,
+        input_schema: &DataSchema,
+        arguments: &Map<String, Value>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            started: Instant::now(),
+            principal: get_principal(&context.extensions).map(|principal| principal.0),
+            session_id: get_session_id(&context.extensions),
+            agent_id,
+            method_name: method_name.to_string(),
+            arguments: redact_data(input_schema, arguments),
+        }
+    }
+
+    pub fn finish(self, status: McpCallOutcome) -> AuditRecord {
+        AuditRecord {
+            timestamp: self.timestamp,
+            principal: self.principal,
+            session_id: self.session_id,
+            agent_id: self.agent_id,
+            method_name: self.method_name,
+            arguments: self.arguments,
+            status,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+pub async fn write_audit_record(audit: Option<&Arc<dyn AuditSink>>, record: AuditRecord) {
+    if let Some(audit) = audit {
+        if let Err(error) = audit.record(&record).await {
+            tracing::error!(%error, ?record, "failed to write audit record");
+        }
+    }
+}
+
+// A record per line, only ever appended to
+pub struct JsonLinesAuditSink {
+    path: PathBuf,
+    file: Mutex<tokio::fs::File>,
+}
+
+impl JsonLinesAuditSink {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(tokio::fs::File::from_std(file)),
+        })
+    }
+}
+
+#[async_trait]
+impl AuditSink for JsonLinesAuditSink {
+    async fn record(&self, record: &AuditRecord) -> Result<(), String> {
+        let mut line = serde_json::to_string(record).map_err(|error| error.to_string())?;
+        line.push('\n');
+
+        // A line is written in one go, so that concurrent calls don't interleave
+        let mut file = self.file.lock().await;
+        file.write_all(line.as_bytes())
+            .await
+            .and(file.flush().await)
+            .map_err(|error| format!("failed to write to {}: {}", self.path.display(), error))
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::golem::{AgentError, AgentInvocation, AgentInvoker};
+use crate::mcp_adaptor::McpMetrics;
+
+pub const MAX_PARKED_REQUESTS: usize = 64;
+
+// `None` if the invocation got cancelled, either before it started (in which case the
+// backend is never called) or mid-flight (in which case the invocation future is dropped)
+pub async fn invoke_cancellable(invoker: &dyn AgentInvoker, invocation: AgentInvocation) -> Option<Result<Value, AgentError>> {
+    let cancellation = invocation.cancellation.clone();
+
+    if cancellation.is_cancelled() {
+        return None;
+    }
+
+    tokio::select! {
+        biased;
+        _ = cancellation.cancelled() => None,
+        result = invoker.invoke(invocation) => Some(result),
+    }
+}
+
+// rmcp sends back whatever a handler returns, even for a request the client cancelled,
+// while the spec says there must be no response. The only way not to respond is to never return,
+// so the handler parks here until the session is gone and whatever it returns goes nowhere.
+// Each parked request holds on to its task until then, so a session parks at most `MAX_PARKED_REQUESTS`
+// and any cancelled past that gets its response after all (see `mcp_parked_requests`)
+pub async fn wait_for_session_end(session_ct: &CancellationToken, parked: &AtomicUsize, metrics: &McpMetrics) {
+    if parked.fetch_add(1, Ordering::SeqCst) >= MAX_PARKED_REQUESTS {
+        parked.fetch_sub(1, Ordering::SeqCst);
+        tracing::warn!(max = MAX_PARKED_REQUESTS, "too many cancelled requests parked in the session, responding to this one");
+        return;
+    }
+
+    let _parked = metrics.track_parked_request();
+    session_ct.cancelled().await;
+
+    // The session is over, no more requests will check on the count
+    parked.fetch_sub(1, Ordering::SeqCst);
+}
+
+// A parked handler keeps the server alive, so its drop guard can't be what ends the session. The initialize request's
+// token is cancelled by rmcp once the service stops, which it does when the transport closes
+pub fn end_session_with_service(service_ct: CancellationToken, session_ct: CancellationToken) {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = session_ct.cancelled() => {}
+            _ = service_ct.cancelled() => session_ct.cancel(),
+        }
+    });
+}
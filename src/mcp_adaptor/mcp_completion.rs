@@ -0,0 +1,256 @@
+use rmcp::model::{CompleteRequestParams, CompletionInfo, Reference};
+
+use crate::golem::{AgentId, AgentMethod, AgentRegistry, ElementSchema};
+use crate::mcp_adaptor::{get_prompt_name, McpAgentCapability};
+
+pub const AGENT_ID_TEMPLATE_VARIABLE: &str = "agent_id";
+
+// Candidate values of a prompt argument or a resource template variable, before filtering by what the user typed.
+// `None` means there is nothing to suggest for it (free text, numbers..)
+pub trait McpCompletionProvider {
+    fn get_candidates(&self, registry: &AgentRegistry) -> Option<Vec<String>>;
+}
+
+impl McpCompletionProvider for ElementSchema {
+    fn get_candidates(&self, _registry: &AgentRegistry) -> Option<Vec<String>> {
+        match self {
+            ElementSchema::Enum(values) => Some(values.clone()),
+            ElementSchema::Bool => Some(vec!["true".to_string(), "false".to_string()]),
+            ElementSchema::String
+            | ElementSchema::U32
+            | ElementSchema::UnstructuredText { .. }
+            | ElementSchema::UnstructuredBinary { .. } => None,
+        }
+    }
+}
+
+// `{agent_id}` of a resource template, suggesting the agents the registry knows of
+pub struct AgentIdCompletion {
+    pub methods: Vec<AgentMethod>,
+}
+
+impl McpCompletionProvider for AgentIdCompletion {
+    fn get_candidates(&self, registry: &AgentRegistry) -> Option<Vec<String>> {
+        // In the global server a method name can belong to more than one agent type
+        let agent_types = registry
+            .get_agent_types()
+            .into_iter()
+            .filter(|agent_type| {
+                registry
+                    .get_agent_methods(agent_type)
+                    .iter()
+                    .any(|method| self.methods.iter().any(|m| m.method_name == method.method_name))
+            })
+            .collect::<Vec<_>>();
+
+        Some(
+            agent_types
+                .iter()
+                .flat_map(|agent_type| registry.get_agents(agent_type))
+                .collect(),
+        )
+    }
+}
+
+// `capabilities` are the ones exposed by the server the request came to, see `get_agent_capabilities`
+pub fn get_completion(
+    request: &CompleteRequestParams,
+    agent_id: Option<&AgentId>,
+    capabilities: Vec<McpAgentCapability>,
+    registry: &AgentRegistry,
+) -> CompletionInfo {
+    let candidates = match &request.r#ref {
+        Reference::Prompt(prompt) => capabilities
+            .into_iter()
+            .find_map(|capability| match capability {
+                McpAgentCapability::Prompt(agent_mcp_prompt)
+                    if get_prompt_name(&agent_mcp_prompt.agent_type, &agent_mcp_prompt.agent_method.method_name, agent_id.is_none())
+                        == prompt.name =>
+                {
+                    Some(agent_mcp_prompt.agent_method)
+                }
+                _ => None,
+            })
+            .and_then(|method| {
+                method
+                    .input_schema
+                    .into_iter()
+                    .find(|entry| entry.name == request.argument.name)
+            })
+            .and_then(|entry| entry.schema.get_candidates(registry)),
+        Reference::Resource(resource) => {
+            if request.argument.name != AGENT_ID_TEMPLATE_VARIABLE {
+                None
+            } else {
+                let methods = capabilities
+                    .into_iter()
+                    .filter_map(|capability| match capability {
+                        McpAgentCapability::Resource(agent_mcp_resource)
+                            if agent_mcp_resource.get_resource_template().uri_template == resource.uri =>
+                        {
+                            Some(agent_mcp_resource.resource)
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                if methods.is_empty() {
+                    None
+                } else {
+                    match agent_id {
+                        // Templates are only listed by the global server, an agent's own server has just the one agent
+                        Some(agent_id) => Some(vec![agent_id.clone()]),
+                        None => AgentIdCompletion { methods }.get_candidates(registry),
+                    }
+                }
+            }
+        }
+    };
+
+    filter_candidates(candidates.unwrap_or_default(), &request.argument.value)
+}
+
+// Case insensitive prefix match, capped at what the spec allows in a single response
+fn filter_candidates(candidates: Vec<String>, value: &str) -> CompletionInfo {
+    let value = value.to_lowercase();
+
+    let matches = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&value))
+        .collect::<Vec<_>>();
+
+    let total = matches.len();
+    let values = matches.into_iter().take(CompletionInfo::MAX_VALUES).collect::<Vec<_>>();
+
+    CompletionInfo {
+        has_more: Some(total > values.len()),
+        total: Some(total as u32),
+        values,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::ArgumentInfo;
+
+    use crate::golem::{AgentMethodExposure, AgentMethodHints, AgentMethodTaskSupport, AgentTypeDefinition, DataSchemaEntry};
+    use crate::mcp_adaptor::McpMappingRules;
+
+    use super::*;
+
+    fn method(method_name: &str, input_schema: Vec<DataSchemaEntry>, exposed_as: Vec<AgentMethodExposure>) -> AgentMethod {
+        AgentMethod {
+            method_name: method_name.to_string(),
+            title: None,
+            description: None,
+            input_schema,
+            output_schema: vec![],
+            hints: AgentMethodHints::default(),
+            task_support: AgentMethodTaskSupport::Forbidden,
+            exposed_as,
+        }
+    }
+
+    // A `report` prompt taking a mode, and a `get_value` resource
+    fn registry() -> AgentRegistry {
+        let mode = ElementSchema::Enum(vec!["fast".to_string(), "Full".to_string(), "slow".to_string()]);
+        let definition = AgentTypeDefinition {
+            constructor_schema: vec![DataSchemaEntry::new("id", ElementSchema::U32)],
+            methods: vec![
+                method("report", vec![DataSchemaEntry::new("mode", mode)], vec![AgentMethodExposure::Prompt]),
+                method("get_value", vec![], vec![]),
+            ],
+        };
+
+        let registry = AgentRegistry::new([("counter".to_string(), definition)].into());
+        for agent_id in ["counter(1)", "counter(2)", "counter(10)"] {
+            registry.add_agent(&agent_id.to_string());
+        }
+        registry
+    }
+
+    fn complete(registry: &AgentRegistry, agent_id: Option<&AgentId>, r#ref: Reference, name: &str, value: &str) -> CompletionInfo {
+        let capabilities = registry
+            .get_agent_type_definitions()
+            .into_iter()
+            .flat_map(|(agent_type, definition)| {
+                definition
+                    .methods
+                    .into_iter()
+                    .flat_map(move |method| McpAgentCapability::from(&agent_type, method, &McpMappingRules::default()))
+            })
+            .collect();
+
+        let request = CompleteRequestParams {
+            meta: None,
+            r#ref,
+            argument: ArgumentInfo {
+                name: name.to_string(),
+                value: value.to_string(),
+            },
+            context: None,
+        };
+
+        get_completion(&request, agent_id, capabilities, registry)
+    }
+
+    #[test]
+    fn candidates_are_matched_by_prefix_ignoring_case() {
+        let candidates = vec!["fast".to_string(), "Full".to_string(), "slow".to_string()];
+
+        let completion = filter_candidates(candidates.clone(), "F");
+        assert_eq!(completion.values, vec!["fast", "Full"]);
+        assert_eq!(completion.total, Some(2));
+        assert_eq!(completion.has_more, Some(false));
+
+        assert_eq!(filter_candidates(candidates, "").values.len(), 3);
+    }
+
+    #[test]
+    fn values_are_capped_with_the_total_and_has_more() {
+        let candidates = (0..CompletionInfo::MAX_VALUES + 20).map(|i| format!("counter({})", i)).collect();
+
+        let completion = filter_candidates(candidates, "counter(");
+
+        assert_eq!(completion.values.len(), CompletionInfo::MAX_VALUES);
+        assert_eq!(completion.total, Some((CompletionInfo::MAX_VALUES + 20) as u32));
+        assert_eq!(completion.has_more, Some(true));
+    }
+
+    #[test]
+    fn prompt_arguments_are_completed_from_their_schema() {
+        let registry = registry();
+
+        let completion = complete(&registry, None, Reference::for_prompt("counter__get_report_prompt"), "mode", "f");
+        assert_eq!(completion.values, vec!["fast", "Full"]);
+
+        // An agent's own server doesn't namespace its prompts
+        let agent_id = "counter(1)".to_string();
+        let completion = complete(&registry, Some(&agent_id), Reference::for_prompt("get_report_prompt"), "mode", "s");
+        assert_eq!(completion.values, vec!["slow"]);
+    }
+
+    #[test]
+    fn unknown_prompts_and_arguments_have_nothing_to_complete() {
+        let registry = registry();
+
+        for (prompt_name, argument_name) in [("get_report_prompt", "mode"), ("counter__get_report_prompt", "speed")] {
+            let completion = complete(&registry, None, Reference::for_prompt(prompt_name), argument_name, "");
+            assert!(completion.values.is_empty());
+            assert_eq!(completion.has_more, Some(false));
+        }
+    }
+
+    #[test]
+    fn resource_template_agent_ids_are_the_known_agents() {
+        let registry = registry();
+        let uri = "golem://agents/{agent_id}/get_value".to_string();
+
+        let completion = complete(&registry, None, Reference::for_resource(uri.clone()), AGENT_ID_TEMPLATE_VARIABLE, "counter(1");
+        assert_eq!(completion.values, vec!["counter(1)", "counter(10)"]);
+
+        let agent_id = "counter(2)".to_string();
+        let completion = complete(&registry, Some(&agent_id), Reference::for_resource(uri), AGENT_ID_TEMPLATE_VARIABLE, "");
+        assert_eq!(completion.values, vec!["counter(2)"]);
+    }
+}
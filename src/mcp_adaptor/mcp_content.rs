@@ -0,0 +1,263 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rmcp::model::{AnnotateAble, CallToolResult, Content, ProtocolVersion, RawAudioContent, RawContent, RawResource, ResourceContents};
+use rmcp::{ErrorData, Peer, RoleServer};
+use serde_json::{Map, Value};
+
+use crate::golem::{AgentId, AgentMethod, DataSchemaEntry, ElementSchema};
+use crate::mcp_adaptor::PROTOCOL_VERSION;
+
+// What the client asked for, capped by what we support
+pub fn get_protocol_version(peer: &Peer<RoleServer>) -> ProtocolVersion {
+    match peer.peer_info() {
+        Some(client_info) if client_info.protocol_version < PROTOCOL_VERSION => client_info.protocol_version.clone(),
+        _ => PROTOCOL_VERSION,
+    }
+}
+
+// Structured outputs go in `structuredContent` (along with its text serialization), and each unstructured
+// output becomes a content item of its own
+pub fn get_tool_result(
+    agent_id: &AgentId,
+    method: &AgentMethod,
+    value: Value,
+    protocol_version: &ProtocolVersion,
+) -> Result<CallToolResult, ErrorData> {
+    let Value::Object(mut fields) = value else {
+        return Ok(CallToolResult::structured(value));
+    };
+
+    let mut content = vec![];
+
+    for DataSchemaEntry { name, schema: element_schema, .. } in method.output_schema.iter() {
+        if element_schema.is_unstructured() {
+            if let Some(value) = fields.remove(name) {
+                let uri = format!("golem://agents/{}/{}#{}", agent_id, method.method_name, name);
+                content.push(get_content(name, &uri, element_schema, value, protocol_version)?);
+            }
+        }
+    }
+
+    let mut result = if !fields.is_empty() || content.is_empty() {
+        CallToolResult::structured(Value::Object(fields))
+    } else {
+        CallToolResult::success(vec![])
+    };
+
+    result.content.extend(content);
+    Ok(result)
+}
+
+fn get_content(
+    name: &str,
+    uri: &str,
+    element_schema: &ElementSchema,
+    value: Value,
+    protocol_version: &ProtocolVersion,
+) -> Result<Content, ErrorData> {
+    let invalid = || ErrorData::internal_error(format!("agent returned an invalid `{}`", name), None);
+
+    let fields = match value {
+        // Plain text needs no wrapping
+        Value::String(text) if matches!(element_schema, ElementSchema::UnstructuredText { .. }) => {
+            return Ok(Content::text(text));
+        }
+        Value::Object(fields) => fields,
+        _ => return Err(invalid()),
+    };
+
+    let mime_type = get_string(&fields, "mime_type").or(match element_schema {
+        ElementSchema::UnstructuredText { mime_type } | ElementSchema::UnstructuredBinary { mime_type } => {
+            mime_type.clone()
+        }
+        _ => None,
+    });
+
+    if let Some(reference_uri) = get_string(&fields, "uri") {
+        // resource_link came with 2025-06-18, older clients get the uri as text
+        if *protocol_version < ProtocolVersion::V_2025_06_18 {
+            return Ok(Content::text(format!("{}: {}", name, reference_uri)));
+        }
+
+        let mut resource = RawResource::new(reference_uri, get_string(&fields, "name").unwrap_or(name.to_string()));
+        resource.mime_type = mime_type;
+        return Ok(Content::resource_link(resource));
+    }
+
+    match (element_schema, fields.get("data")) {
+        (ElementSchema::UnstructuredText { .. }, Some(Value::String(text))) => match mime_type.as_deref() {
+            None | Some("text/plain") => Ok(Content::text(text.clone())),
+            Some(_) => Ok(Content::resource(ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type,
+                text: text.clone(),
+                meta: None,
+            })),
+        },
+        (ElementSchema::UnstructuredBinary { .. }, Some(data)) => {
+            let data = get_base64(data).ok_or_else(invalid)?;
+            let mime_type = mime_type.unwrap_or("application/octet-stream".to_string());
+
+            // audio came with 2025-03-26, older clients get it as an embedded blob
+            if mime_type.starts_with("image/") {
+                Ok(Content::image(data, mime_type))
+            } else if mime_type.starts_with("audio/") && *protocol_version >= ProtocolVersion::V_2025_03_26 {
+                Ok(RawContent::Audio(RawAudioContent { data, mime_type }).no_annotation())
+            } else {
+                Ok(Content::resource(ResourceContents::BlobResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(mime_type),
+                    blob: data,
+                    meta: None,
+                }))
+            }
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn get_string(fields: &Map<String, Value>, key: &str) -> Option<String> {
+    fields.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+// Binary data is either base64 already, or an array of bytes
+fn get_base64(data: &Value) -> Option<String> {
+    match data {
+        Value::String(data) => Some(data.clone()),
+        Value::Array(bytes) => bytes
+            .iter()
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .map(|bytes| BASE64.encode(bytes)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::golem::{AgentMethodHints, AgentMethodTaskSupport};
+
+    use super::*;
+
+    fn method(output_schema: Vec<DataSchemaEntry>) -> AgentMethod {
+        AgentMethod {
+            method_name: "render".to_string(),
+            title: None,
+            description: None,
+            input_schema: vec![],
+            output_schema,
+            hints: AgentMethodHints::default(),
+            task_support: AgentMethodTaskSupport::Forbidden,
+            exposed_as: vec![],
+        }
+    }
+
+    fn text(mime_type: Option<&str>) -> ElementSchema {
+        ElementSchema::UnstructuredText { mime_type: mime_type.map(str::to_string) }
+    }
+
+    fn binary(mime_type: Option<&str>) -> ElementSchema {
+        ElementSchema::UnstructuredBinary { mime_type: mime_type.map(str::to_string) }
+    }
+
+    // The content items of a method with a single unstructured output, as json
+    fn get_content_json(schema: ElementSchema, value: Value, protocol_version: ProtocolVersion) -> Value {
+        let method = method(vec![DataSchemaEntry::new("output", schema)]);
+        let result = get_tool_result(&"renderer(1)".to_string(), &method, json!({"output": value}), &protocol_version).unwrap();
+
+        assert_eq!(result.structured_content, None);
+        serde_json::to_value(result.content).unwrap()
+    }
+
+    #[test]
+    fn structured_outputs_stay_structured() {
+        let method = method(vec![DataSchemaEntry::new("result", ElementSchema::U32)]);
+        let result = get_tool_result(&"counter(1)".to_string(), &method, json!({"result": 3}), &PROTOCOL_VERSION).unwrap();
+
+        assert_eq!(result.structured_content, Some(json!({"result": 3})));
+        assert_eq!(serde_json::to_value(result.content).unwrap(), json!([{"type": "text", "text": "{\"result\":3}"}]));
+    }
+
+    #[test]
+    fn unstructured_outputs_become_content_next_to_the_structured_ones() {
+        let method = method(vec![
+            DataSchemaEntry::new("summary", text(None)),
+            DataSchemaEntry::new("pages", ElementSchema::U32),
+        ]);
+        let value = json!({"summary": "all good", "pages": 2});
+
+        let result = get_tool_result(&"renderer(1)".to_string(), &method, value, &PROTOCOL_VERSION).unwrap();
+
+        assert_eq!(result.structured_content, Some(json!({"pages": 2})));
+        assert_eq!(
+            serde_json::to_value(result.content).unwrap(),
+            json!([{"type": "text", "text": "{\"pages\":2}"}, {"type": "text", "text": "all good"}])
+        );
+    }
+
+    #[test]
+    fn text_with_a_mime_type_is_an_embedded_resource() {
+        assert_eq!(
+            get_content_json(text(Some("text/markdown")), json!({"data": "# Title"}), PROTOCOL_VERSION),
+            json!([{
+                "type": "resource",
+                "resource": {"uri": "golem://agents/renderer(1)/render#output", "mimeType": "text/markdown", "text": "# Title"}
+            }])
+        );
+    }
+
+    #[test]
+    fn binary_is_an_image_audio_or_blob_by_its_mime_type() {
+        assert_eq!(
+            get_content_json(binary(Some("image/png")), json!({"data": [1, 2, 3]}), PROTOCOL_VERSION),
+            json!([{"type": "image", "data": "AQID", "mimeType": "image/png"}])
+        );
+        assert_eq!(
+            get_content_json(binary(None), json!({"data": "AQID", "mime_type": "audio/wav"}), PROTOCOL_VERSION),
+            json!([{"type": "audio", "data": "AQID", "mimeType": "audio/wav"}])
+        );
+        assert_eq!(
+            get_content_json(binary(None), json!({"data": "AQID"}), PROTOCOL_VERSION),
+            json!([{
+                "type": "resource",
+                "resource": {"uri": "golem://agents/renderer(1)/render#output", "mimeType": "application/octet-stream", "blob": "AQID"}
+            }])
+        );
+    }
+
+    #[test]
+    fn older_clients_get_what_they_know() {
+        assert_eq!(
+            get_content_json(binary(Some("audio/wav")), json!({"data": "AQID"}), ProtocolVersion::V_2024_11_05)[0]["type"],
+            "resource"
+        );
+        assert_eq!(
+            get_content_json(text(None), json!({"uri": "https://example.com/report"}), ProtocolVersion::V_2025_03_26),
+            json!([{"type": "text", "text": "output: https://example.com/report"}])
+        );
+    }
+
+    #[test]
+    fn references_are_resource_links() {
+        assert_eq!(
+            get_content_json(
+                binary(Some("application/pdf")),
+                json!({"uri": "https://example.com/report.pdf", "name": "report"}),
+                PROTOCOL_VERSION
+            ),
+            json!([{"type": "resource_link", "uri": "https://example.com/report.pdf", "name": "report", "mimeType": "application/pdf"}])
+        );
+    }
+
+    #[test]
+    fn malformed_outputs_are_an_error() {
+        let method = method(vec![DataSchemaEntry::new("output", binary(None))]);
+
+        for value in [json!(3), json!({"data": [256]}), json!({"data": true})] {
+            let result = get_tool_result(&"renderer(1)".to_string(), &method, json!({"output": value}), &PROTOCOL_VERSION);
+            assert!(result.is_err());
+        }
+    }
+}
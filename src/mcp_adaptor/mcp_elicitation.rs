@@ -0,0 +1,120 @@
+use async_trait::async_trait;
+use rmcp::model::{CreateElicitationRequestParams, ElicitationAction, ElicitationSchema, EnumSchema};
+use rmcp::service::ElicitationMode;
+use rmcp::{Peer, RoleServer};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use crate::golem::{AgentElicitation, AgentElicitationResponse, AgentElicitor, DataSchema, DataSchemaEntry, ElementSchema, ElicitationRequester, validate_data};
+
+// Sends the agent's elicitation to the client as `elicitation/create`, and waits for the user's answer
+pub struct McpElicitor {
+    peer: Peer<RoleServer>,
+}
+
+impl McpElicitor {
+    // Only clients that advertised (form) elicitation during initialization get asked anything
+    pub fn requester(peer: Peer<RoleServer>) -> ElicitationRequester {
+        if peer.supported_elicitation_modes().contains(&ElicitationMode::Form) {
+            ElicitationRequester::new(Arc::new(McpElicitor { peer }))
+        } else {
+            ElicitationRequester::default()
+        }
+    }
+}
+
+#[async_trait]
+impl AgentElicitor for McpElicitor {
+    async fn elicit(&self, elicitation: AgentElicitation) -> Result<AgentElicitationResponse, String> {
+        let params = CreateElicitationRequestParams::FormElicitationParams {
+            meta: None,
+            message: elicitation.message,
+            requested_schema: get_elicitation_schema(&elicitation.schema),
+        };
+
+        let result = self
+            .peer
+            .create_elicitation(params)
+            .await
+            .map_err(|e| format!("elicitation failed: {}", e))?;
+
+        match result.action {
+            ElicitationAction::Accept => {
+                let content = match result.content {
+                    Some(Value::Object(content)) => content,
+                    None => Map::new(),
+                    Some(other) => return Err(format!("elicitation response is not an object: {}", other)),
+                };
+
+                // Clients are supposed to validate against the requested schema, but the backend shouldn't have to trust that
+                validate_data(&elicitation.schema, &content)
+                    .map_err(|error| format!("elicitation response has {}", error))?;
+                Ok(AgentElicitationResponse::Accepted(content))
+            }
+            ElicitationAction::Decline => Ok(AgentElicitationResponse::Declined),
+            ElicitationAction::Cancel => Ok(AgentElicitationResponse::Cancelled),
+        }
+    }
+}
+
+// Elicitation only allows a flat object of primitives, which is all a `DataSchema` is for now
+pub fn get_elicitation_schema(schema: &DataSchema) -> ElicitationSchema {
+    schema
+        .iter()
+        .fold(ElicitationSchema::builder(), |builder, DataSchemaEntry { name, schema, .. }| match schema {
+            // Elicitation has no notion of files, the closest is asking for a string
+            ElementSchema::String | ElementSchema::UnstructuredText { .. } | ElementSchema::UnstructuredBinary { .. } => {
+                builder.required_string(name)
+            }
+            ElementSchema::U32 => builder.required_integer(name, 0, u32::MAX as i64),
+            ElementSchema::Bool => builder.required_bool(name),
+            ElementSchema::Enum(values) => builder.required_enum_schema(name, EnumSchema::builder(values.clone()).build()),
+        })
+        .build_unchecked()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn every_entry_is_a_required_primitive() {
+        let schema = vec![
+            DataSchemaEntry::new("name", ElementSchema::String),
+            DataSchemaEntry::new("count", ElementSchema::U32),
+            DataSchemaEntry::new("confirm", ElementSchema::Bool),
+            DataSchemaEntry::new("color", ElementSchema::Enum(vec!["red".to_string(), "green".to_string()])),
+        ];
+
+        let schema = serde_json::to_value(get_elicitation_schema(&schema)).unwrap();
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "count": {"type": "integer", "minimum": 0, "maximum": u32::MAX},
+                    "confirm": {"type": "boolean"},
+                    "color": {"type": "string", "enum": ["red", "green"]}
+                },
+                "required": ["name", "count", "confirm", "color"]
+            })
+        );
+    }
+
+    #[test]
+    fn unstructured_entries_are_asked_for_as_a_required_string() {
+        let schema = vec![
+            DataSchemaEntry::new("notes", ElementSchema::UnstructuredText { mime_type: Some("text/markdown".to_string()) }),
+            DataSchemaEntry::new("attachment", ElementSchema::UnstructuredBinary { mime_type: None }),
+        ];
+
+        let schema = serde_json::to_value(get_elicitation_schema(&schema)).unwrap();
+
+        assert_eq!(schema["properties"], json!({"notes": {"type": "string"}, "attachment": {"type": "string"}}));
+        assert_eq!(schema["required"], json!(["notes", "attachment"]));
+    }
+}
@@ -0,0 +1,87 @@
+use rmcp::model::{CallToolResult, Content, ErrorCode};
+use rmcp::ErrorData;
+use serde_json::json;
+
+use crate::golem::AgentError;
+
+// Stable codes for the protocol errors that don't have a json-rpc (or mcp) code of their own,
+// within the range json-rpc leaves for implementation defined server errors
+pub const AGENT_NOT_FOUND: ErrorCode = ErrorCode(-32004);
+pub const AGENT_BACKEND_UNAVAILABLE: ErrorCode = ErrorCode(-32005);
+pub const RATE_LIMITED: ErrorCode = ErrorCode(-32006);
+pub const AGENT_OVERLOADED: ErrorCode = ErrorCode(-32007);
+
+// Failures of the agent itself go back to the model as a tool error (`isError: true`), as it may be able
+// to do something about them, e.g fix the arguments or retry. Anything else is a protocol error
+pub fn get_call_tool_result(error: AgentError) -> Result<CallToolResult, ErrorData> {
+    match error {
+        AgentError::InvalidArguments { .. }
+        | AgentError::Trapped { .. }
+        | AgentError::Timeout { .. }
+        | AgentError::RateLimited { .. }
+        | AgentError::Overloaded { .. } => {
+            Ok(CallToolResult {
+                content: vec![Content::text(error.to_string())],
+                structured_content: Some(json!({"error": error})),
+                is_error: Some(true),
+                meta: None,
+            })
+        }
+        AgentError::AgentNotFound { .. }
+        | AgentError::MethodNotFound { .. }
+        | AgentError::BackendUnavailable { .. }
+        | AgentError::Unsupported { .. } => Err(get_error_data(&error)),
+    }
+}
+
+// For everything that has no tool level error to fall back to, e.g resource reads
+pub fn get_error_data(error: &AgentError) -> ErrorData {
+    let code = match error {
+        AgentError::AgentNotFound { .. } => AGENT_NOT_FOUND,
+        // Unknown tools are invalid params as per the spec, `METHOD_NOT_FOUND` is for json-rpc methods
+        AgentError::MethodNotFound { .. } | AgentError::InvalidArguments { .. } => ErrorCode::INVALID_PARAMS,
+        // ...and for operations the backend doesn't have, so that clients don't take it for a transient failure
+        AgentError::Unsupported { .. } => ErrorCode::METHOD_NOT_FOUND,
+        AgentError::BackendUnavailable { .. } => AGENT_BACKEND_UNAVAILABLE,
+        AgentError::RateLimited { .. } => RATE_LIMITED,
+        AgentError::Overloaded { .. } => AGENT_OVERLOADED,
+        AgentError::Trapped { .. } | AgentError::Timeout { .. } => ErrorCode::INTERNAL_ERROR,
+    };
+
+    ErrorData::new(code, error.to_string(), Some(json!({"error": error})))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use serde_json::Value;
+
+    use crate::golem::{AgentInvocation, AgentInvoker};
+
+    use super::*;
+
+    // Can't manage agents, like most backends
+    struct InvokeOnlyInvoker;
+
+    #[async_trait]
+    impl AgentInvoker for InvokeOnlyInvoker {
+        async fn invoke(&self, _invocation: AgentInvocation) -> Result<Value, AgentError> {
+            Ok(Value::Null)
+        }
+    }
+
+    #[tokio::test]
+    async fn unsupported_lifecycle_operation_is_not_a_transient_error() {
+        let error = InvokeOnlyInvoker.list_agents(&"counter".to_string()).await.unwrap_err();
+        assert!(matches!(error, AgentError::Unsupported { .. }));
+
+        let error_data = get_call_tool_result(error).unwrap_err();
+        assert_eq!(error_data.code, ErrorCode::METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn unavailable_backend_is_a_transient_error() {
+        let error = AgentError::BackendUnavailable { message: "connection refused".to_string() };
+        assert_eq!(get_call_tool_result(error).unwrap_err().code, AGENT_BACKEND_UNAVAILABLE);
+    }
+}
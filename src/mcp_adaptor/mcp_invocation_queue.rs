@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::golem::{get_agent_type, AgentError, AgentId, AgentInvocation, AgentInvoker};
+use crate::mcp_adaptor::{invoke_cancellable, McpMetrics};
+
+// How many invocations run at once and how many can wait for their turn. Nothing is limited by default
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpInvocationLimits {
+    // Invocations of one agent that can be pending at once, running or waiting to
+    pub max_queue_depth: Option<usize>,
+    // One invocation at a time per agent, in the order they came in. Golem runs them one by one anyway,
+    // this keeps them from piling up in the backend instead
+    pub serialize_per_agent: bool,
+    // Invocations running at once, across every agent
+    pub max_concurrent: Option<usize>,
+    // What happens to an invocation that can't start right away, and how long it may wait
+    pub overflow: QueueOverflow,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflow {
+    // Fails right away, as a tool error the model can retry later, unless it can start at once: its agent's queue,
+    // its agent's turn (with `serialize_per_agent`) and the global cap all have to have room
+    #[default]
+    Reject,
+    // Waits for room in the queue and its turn, and fails if it hasn't started by then
+    Wait { timeout_ms: u64 },
+}
+
+impl McpInvocationLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_queue_depth == Some(0) || self.max_concurrent == Some(0) {
+            return Err("needs a positive max_queue_depth and max_concurrent, leave them out for no limit".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+// Where agent invocations wait for their turn, see `McpInvocationLimits`
+#[derive(Clone)]
+pub struct McpInvocationQueue {
+    limits: McpInvocationLimits,
+    agents: Arc<Mutex<HashMap<AgentId, AgentQueue>>>,
+    running: Option<Arc<Semaphore>>,
+    metrics: McpMetrics,
+}
+
+// Only there while the agent has pending invocations
+struct AgentQueue {
+    pending: usize,
+    slots: Option<Arc<Semaphore>>,
+    turn: Option<Arc<Semaphore>>,
+}
+
+// Holds the invocation's place until it is done
+struct InvocationPermit {
+    queue: McpInvocationQueue,
+    agent_id: AgentId,
+    slot: Option<OwnedSemaphorePermit>,
+    turn: Option<OwnedSemaphorePermit>,
+    running: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for InvocationPermit {
+    fn drop(&mut self) {
+        let mut agents = self.queue.agents.lock().unwrap();
+
+        if let Some(agent_queue) = agents.get_mut(&self.agent_id) {
+            agent_queue.pending -= 1;
+
+            if agent_queue.pending == 0 {
+                agents.remove(&self.agent_id);
+            }
+        }
+    }
+}
+
+impl McpInvocationQueue {
+    pub fn new(limits: McpInvocationLimits, metrics: McpMetrics) -> Self {
+        Self {
+            running: limits.max_concurrent.map(|max_concurrent| Arc::new(Semaphore::new(max_concurrent))),
+            limits,
+            agents: Arc::new(Mutex::new(HashMap::new())),
+            metrics,
+        }
+    }
+
+    // Like `invoke_cancellable`, once it is the invocation's turn. Cancelling it while it waits takes it out of the queue
+    pub async fn invoke(&self, invoker: &dyn AgentInvoker, invocation: AgentInvocation) -> Option<Result<Value, AgentError>> {
+        let agent_type = get_agent_type(&invocation.agent_id);
+        let _pending = self.metrics.track_invocation(&agent_type);
+        let cancellation = invocation.cancellation.clone();
+        let queued_at = Instant::now();
+
+        let permit = tokio::select! {
+            biased;
+            _ = cancellation.cancelled() => return None,
+            permit = self.acquire(&invocation.agent_id) => permit,
+        };
+
+        let permit = match permit {
+            Ok(permit) => permit,
+            Err(error) => {
+                self.metrics.observe_invocation_rejected(&agent_type);
+                return Some(Err(error));
+            }
+        };
+
+        self.metrics.observe_invocation_wait(&agent_type, queued_at.elapsed());
+
+        let running = self.metrics.track_running_invocation();
+        let result = invoke_cancellable(invoker, invocation).await;
+        drop(running);
+        drop(permit);
+
+        result
+    }
+
+    // Invocations of the agent running or waiting to, none once its queue is gone
+    pub fn get_pending(&self, agent_id: &AgentId) -> usize {
+        self.agents.lock().unwrap().get(agent_id).map(|agent_queue| agent_queue.pending).unwrap_or_default()
+    }
+
+    // With `QueueOverflow::Wait` the timeout is for the whole wait, a stuck invocation ahead of this one included.
+    // With `QueueOverflow::Reject` there is no wait at all
+    async fn acquire(&self, agent_id: &AgentId) -> Result<InvocationPermit, AgentError> {
+        match self.limits.overflow {
+            QueueOverflow::Reject => self.wait_for_turn(agent_id).await,
+            QueueOverflow::Wait { timeout_ms } => {
+                match tokio::time::timeout(Duration::from_millis(timeout_ms), self.wait_for_turn(agent_id)).await {
+                    Ok(permit) => permit,
+                    Err(_) => Err(AgentError::Overloaded {
+                        message: format!("{} did not get a turn within {}ms", agent_id, timeout_ms),
+                    }),
+                }
+            }
+        }
+    }
+
+    async fn wait_for_turn(&self, agent_id: &AgentId) -> Result<InvocationPermit, AgentError> {
+        let (slots, turn) = {
+            let mut agents = self.agents.lock().unwrap();
+            let agent_queue = agents.entry(agent_id.clone()).or_insert_with(|| AgentQueue {
+                pending: 0,
+                slots: self.limits.max_queue_depth.map(|max_queue_depth| Arc::new(Semaphore::new(max_queue_depth))),
+                turn: self.limits.serialize_per_agent.then(|| Arc::new(Semaphore::new(1))),
+            });
+            agent_queue.pending += 1;
+
+            (agent_queue.slots.clone(), agent_queue.turn.clone())
+        };
+
+        // Counted as pending from here on, the permit takes it out again however this ends
+        let mut permit = InvocationPermit {
+            queue: self.clone(),
+            agent_id: agent_id.clone(),
+            slot: None,
+            turn: None,
+            running: None,
+        };
+
+        if let Some(slots) = slots {
+            let message = || {
+                format!(
+                    "{} already has {} invocations pending",
+                    agent_id,
+                    self.limits.max_queue_depth.unwrap_or_default()
+                )
+            };
+            permit.slot = Some(self.take(slots, message).await?);
+        }
+
+        if let Some(turn) = turn {
+            permit.turn = Some(self.take(turn, || format!("{} is busy with another invocation", agent_id)).await?);
+        }
+
+        if let Some(running) = &self.running {
+            let message = || {
+                format!(
+                    "{} invocations are already running",
+                    self.limits.max_concurrent.unwrap_or_default()
+                )
+            };
+            permit.running = Some(self.take(running.clone(), message).await?);
+        }
+
+        Ok(permit)
+    }
+
+    // Waits for the permit, unless the overflow policy says to turn the invocation down instead
+    async fn take(&self, semaphore: Arc<Semaphore>, message: impl FnOnce() -> String) -> Result<OwnedSemaphorePermit, AgentError> {
+        match self.limits.overflow {
+            QueueOverflow::Reject => semaphore
+                .try_acquire_owned()
+                .map_err(|_| AgentError::Overloaded { message: message() }),
+            QueueOverflow::Wait { .. } => Ok(semaphore.acquire_owned().await.expect("agent queue semaphores are never closed")),
+        }
+    }
+}
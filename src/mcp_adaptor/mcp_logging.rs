@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+// Spans with this field route the events inside them to the logger with that id, see `GolemAgentMcpServer::request_span`
+pub const MCP_LOGGER_FIELD: &str = "mcp_logger";
+
+pub type McpLoggerId = String;
+
+// The level a session asked for with `logging/setLevel`, and the channel to its peer.
+// Messages go through a single forwarding task so that the client sees them in order
+#[derive(Clone)]
+pub struct McpLogger {
+    level: Arc<RwLock<LoggingLevel>>,
+    sender: mpsc::UnboundedSender<LoggingMessageNotificationParam>,
+}
+
+impl McpLogger {
+    fn new(peer: Peer<RoleServer>, level: LoggingLevel) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<LoggingMessageNotificationParam>();
+
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if peer.notify_logging_message(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            level: Arc::new(RwLock::new(level)),
+            sender,
+        }
+    }
+
+    pub fn is_enabled(&self, level: LoggingLevel) -> bool {
+        get_severity(level) >= get_severity(*self.level.read().unwrap())
+    }
+
+    pub fn log(&self, level: LoggingLevel, logger: Option<String>, data: Value) {
+        if self.is_enabled(level) {
+            let _ = self.sender.send(LoggingMessageNotificationParam { level, logger, data });
+        }
+    }
+}
+
+// Sessions that turned on logging. Nothing is forwarded to a session until it calls `logging/setLevel`
+#[derive(Clone, Default)]
+pub struct McpLoggers {
+    loggers: Arc<RwLock<HashMap<McpLoggerId, McpLogger>>>,
+}
+
+impl McpLoggers {
+    // Returns true if this is the first time the session set a level
+    pub fn set_level(&self, logger_id: &McpLoggerId, peer: Peer<RoleServer>, level: LoggingLevel) -> bool {
+        let mut loggers = self.loggers.write().unwrap();
+
+        match loggers.get(logger_id) {
+            Some(logger) => {
+                *logger.level.write().unwrap() = level;
+                false
+            }
+            None => {
+                loggers.insert(logger_id.clone(), McpLogger::new(peer, level));
+                true
+            }
+        }
+    }
+
+    pub fn get(&self, logger_id: &McpLoggerId) -> Option<McpLogger> {
+        self.loggers.read().unwrap().get(logger_id).cloned()
+    }
+
+    pub fn remove(&self, logger_id: &McpLoggerId) {
+        self.loggers.write().unwrap().remove(logger_id);
+    }
+}
+
+// A `tracing` layer forwarding the events of a session's request handling (agent invocations included)
+// as `notifications/message`. Events outside of a request span stay local
+pub struct McpLoggingLayer {
+    loggers: McpLoggers,
+}
+
+impl McpLoggingLayer {
+    pub fn new(loggers: McpLoggers) -> Self {
+        Self { loggers }
+    }
+}
+
+struct McpLoggerSpan(McpLoggerId);
+
+impl<S> Layer<S> for McpLoggingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = JsonVisitor::default();
+        attrs.record(&mut visitor);
+
+        if let (Some(Value::String(logger_id)), Some(span)) = (visitor.fields.remove(MCP_LOGGER_FIELD), ctx.span(id)) {
+            span.extensions_mut().insert(McpLoggerSpan(logger_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+
+        let Some(logger) = scope
+            .filter_map(|span| span.extensions().get::<McpLoggerSpan>().map(|logger| logger.0.clone()))
+            .find_map(|logger_id| self.loggers.get(&logger_id))
+        else {
+            return;
+        };
+
+        let level = get_logging_level(event.metadata().level());
+
+        if logger.is_enabled(level) {
+            let mut visitor = JsonVisitor::default();
+            event.record(&mut visitor);
+
+            logger.log(
+                level,
+                Some(event.metadata().target().to_string()),
+                Value::Object(visitor.fields),
+            );
+        }
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor {
+    fields: Map<String, Value>,
+}
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields.insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+// tracing has no notice/critical/alert/emergency, and trace is as chatty as mcp gets
+pub fn get_logging_level(level: &Level) -> LoggingLevel {
+    match *level {
+        Level::ERROR => LoggingLevel::Error,
+        Level::WARN => LoggingLevel::Warning,
+        Level::INFO => LoggingLevel::Info,
+        Level::DEBUG | Level::TRACE => LoggingLevel::Debug,
+    }
+}
+
+fn get_severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
@@ -0,0 +1,447 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use rmcp::model::{ClientNotification, ClientRequest, ServerInfo, ServerResult};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{ErrorData, RoleServer, Service};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::golem::{get_agent_type, AgentId, AgentType};
+use crate::mcp_adaptor::RateLimitScope;
+
+// Label value for the sessions of the global server, which aren't tied to an agent type
+const GLOBAL_AGENT_TYPE: &str = "global";
+
+// How a call to an agent went, as a metric label and in the audit log
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpCallOutcome {
+    Ok,
+    // The agent (or the adaptor on its behalf) reported an error as the tool result
+    ToolError,
+    Error,
+    Cancelled,
+    // Turned down before it got to the agent, see `McpRateLimits`
+    RateLimited,
+}
+
+impl McpCallOutcome {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            McpCallOutcome::Ok => "ok",
+            McpCallOutcome::ToolError => "tool_error",
+            McpCallOutcome::Error => "error",
+            McpCallOutcome::Cancelled => "cancelled",
+            McpCallOutcome::RateLimited => "rate_limited",
+        }
+    }
+}
+
+// What clients do and how agents respond, in the prometheus text format (see `encode`).
+// Cheap to clone, every clone records into the same registry
+#[derive(Clone)]
+pub struct McpMetrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    tool_calls: IntCounterVec,
+    tool_call_duration: HistogramVec,
+    invocation_queue_depth: IntGaugeVec,
+    invocations_running: IntGauge,
+    invocation_wait: HistogramVec,
+    invocations_rejected: IntCounterVec,
+    sessions_created: IntCounterVec,
+    sessions_closed: IntCounterVec,
+    active_sessions: IntGaugeVec,
+    active_sse_streams: IntGauge,
+    parked_requests: IntGauge,
+    rate_limited: IntCounterVec,
+}
+
+impl Default for McpMetrics {
+    fn default() -> Self {
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "mcp_requests_total",
+                "JSON-RPC requests by method and outcome",
+            ),
+            &["method", "outcome"],
+        )
+        .unwrap();
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mcp_request_duration_seconds",
+                "JSON-RPC request latency by method",
+            ),
+            &["method"],
+        )
+        .unwrap();
+        let tool_calls = IntCounterVec::new(
+            Opts::new(
+                "mcp_tool_calls_total",
+                "Tool calls by tool, agent type and outcome",
+            ),
+            &["tool", "agent_type", "outcome"],
+        )
+        .unwrap();
+        let tool_call_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mcp_tool_call_duration_seconds",
+                "Tool call latency by tool and agent type",
+            ),
+            &["tool", "agent_type"],
+        )
+        .unwrap();
+        let invocation_queue_depth = IntGaugeVec::new(
+            Opts::new(
+                "mcp_invocation_queue_depth",
+                "Agent invocations waiting for their turn or running, by agent type",
+            ),
+            &["agent_type"],
+        )
+        .unwrap();
+        let invocations_running = IntGauge::new(
+            "mcp_invocations_running",
+            "Agent invocations running, across agent types",
+        )
+        .unwrap();
+        let invocation_wait = HistogramVec::new(
+            HistogramOpts::new(
+                "mcp_invocation_wait_seconds",
+                "Time agent invocations spent queued, by agent type",
+            ),
+            &["agent_type"],
+        )
+        .unwrap();
+        let invocations_rejected = IntCounterVec::new(
+            Opts::new(
+                "mcp_invocations_rejected_total",
+                "Agent invocations turned down by a full queue, by agent type",
+            ),
+            &["agent_type"],
+        )
+        .unwrap();
+        let sessions_created = IntCounterVec::new(
+            Opts::new(
+                "mcp_sessions_created_total",
+                "Sessions initialized, by agent type",
+            ),
+            &["agent_type"],
+        )
+        .unwrap();
+        let sessions_closed = IntCounterVec::new(
+            Opts::new(
+                "mcp_sessions_closed_total",
+                "Sessions that ended or expired, by agent type",
+            ),
+            &["agent_type"],
+        )
+        .unwrap();
+        let active_sessions = IntGaugeVec::new(
+            Opts::new("mcp_active_sessions", "Live sessions, by agent type"),
+            &["agent_type"],
+        )
+        .unwrap();
+        let active_sse_streams =
+            IntGauge::new("mcp_active_sse_streams", "Open SSE response streams").unwrap();
+        let parked_requests = IntGauge::new(
+            "mcp_parked_requests",
+            "Cancelled requests held until their session ends, so that they get no response",
+        )
+        .unwrap();
+        let rate_limited = IntCounterVec::new(
+            Opts::new(
+                "mcp_rate_limited_total",
+                "Tool calls and http requests turned down, by the limit they hit",
+            ),
+            &["scope"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry
+            .register(Box::new(request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(tool_calls.clone())).unwrap();
+        registry
+            .register(Box::new(tool_call_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(invocation_queue_depth.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(invocations_running.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(invocation_wait.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(invocations_rejected.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(sessions_created.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(sessions_closed.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(active_sessions.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(active_sse_streams.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(parked_requests.clone()))
+            .unwrap();
+        registry.register(Box::new(rate_limited.clone())).unwrap();
+
+        Self {
+            registry,
+            requests,
+            request_duration,
+            tool_calls,
+            tool_call_duration,
+            invocation_queue_depth,
+            invocations_running,
+            invocation_wait,
+            invocations_rejected,
+            sessions_created,
+            sessions_closed,
+            active_sessions,
+            active_sse_streams,
+            parked_requests,
+            rate_limited,
+        }
+    }
+}
+
+impl McpMetrics {
+    pub fn observe_request(&self, method: &str, outcome: &str, duration: Duration) {
+        self.requests.with_label_values(&[method, outcome]).inc();
+        self.request_duration
+            .with_label_values(&[method])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn observe_tool_call(
+        &self,
+        tool: &str,
+        agent_type: &AgentType,
+        outcome: McpCallOutcome,
+        duration: Duration,
+    ) {
+        self.tool_calls
+            .with_label_values(&[tool, agent_type, outcome.get_name()])
+            .inc();
+        self.tool_call_duration
+            .with_label_values(&[tool, agent_type])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn observe_rate_limited(&self, scope: RateLimitScope) {
+        self.rate_limited
+            .with_label_values(&[scope.get_name()])
+            .inc();
+    }
+
+    // Counts the invocation until the guard is dropped
+    pub fn track_invocation(&self, agent_type: &AgentType) -> McpGaugeGuard {
+        McpGaugeGuard::new(self.invocation_queue_depth.with_label_values(&[agent_type]))
+    }
+
+    pub fn track_running_invocation(&self) -> McpGaugeGuard {
+        McpGaugeGuard::new(self.invocations_running.clone())
+    }
+
+    pub fn observe_invocation_wait(&self, agent_type: &AgentType, duration: Duration) {
+        self.invocation_wait
+            .with_label_values(&[agent_type])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn observe_invocation_rejected(&self, agent_type: &AgentType) {
+        self.invocations_rejected
+            .with_label_values(&[agent_type])
+            .inc();
+    }
+
+    pub fn track_sse_stream(&self) -> McpGaugeGuard {
+        McpGaugeGuard::new(self.active_sse_streams.clone())
+    }
+
+    pub fn track_parked_request(&self) -> McpGaugeGuard {
+        McpGaugeGuard::new(self.parked_requests.clone())
+    }
+
+    pub fn session_created(&self, agent_id: &Option<AgentId>) {
+        self.sessions_created
+            .with_label_values(&[&get_agent_type_label(agent_id)])
+            .inc();
+    }
+
+    pub fn session_closed(&self, agent_id: &Option<AgentId>) {
+        self.sessions_closed
+            .with_label_values(&[&get_agent_type_label(agent_id)])
+            .inc();
+    }
+
+    // Sessions are counted when scraped rather than kept up to date, so that the gauge can't drift
+    pub fn set_active_sessions(&self, sessions: &BTreeMap<Option<AgentId>, usize>) {
+        self.active_sessions.reset();
+
+        for (agent_id, count) in sessions {
+            self.active_sessions
+                .with_label_values(&[&get_agent_type_label(agent_id)])
+                .add(*count as i64);
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap_or_else(|error| tracing::error!(%error, "failed to encode metrics"));
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+fn get_agent_type_label(agent_id: &Option<AgentId>) -> String {
+    agent_id
+        .as_ref()
+        .map(get_agent_type)
+        .unwrap_or(GLOBAL_AGENT_TYPE.to_string())
+}
+
+pub struct McpGaugeGuard {
+    gauge: IntGauge,
+}
+
+impl McpGaugeGuard {
+    fn new(gauge: IntGauge) -> Self {
+        gauge.inc();
+        Self { gauge }
+    }
+}
+
+impl Drop for McpGaugeGuard {
+    fn drop(&mut self) {
+        self.gauge.dec();
+    }
+}
+
+// Times every request by its json-rpc method. There is no single place in `ServerHandler` that sees them all,
+// hence wrapping the server (or another wrapper of it) as a whole
+#[derive(Clone)]
+pub struct McpMeteredServer<S> {
+    inner: S,
+    metrics: McpMetrics,
+}
+
+impl<S> McpMeteredServer<S> {
+    pub fn new(inner: S, metrics: McpMetrics) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+impl<S: Service<RoleServer>> Service<RoleServer> for McpMeteredServer<S> {
+    async fn handle_request(
+        &self,
+        request: ClientRequest,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ServerResult, ErrorData> {
+        let method = request.method().to_string();
+        let start = Instant::now();
+
+        let result = self.inner.handle_request(request, context).await;
+
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        self.metrics
+            .observe_request(&method, outcome, start.elapsed());
+
+        result
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: ClientNotification,
+        context: NotificationContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.inner.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        self.inner.get_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observations_show_up_in_the_registry_output() {
+        let metrics = McpMetrics::default();
+        let clone = metrics.clone();
+
+        metrics.observe_request("tools/call", "ok", Duration::from_millis(5));
+        clone.observe_tool_call(
+            "increment",
+            &"counter".to_string(),
+            McpCallOutcome::ToolError,
+            Duration::from_millis(5),
+        );
+        metrics.observe_rate_limited(RateLimitScope::Session);
+        metrics.session_created(&Some("counter(1)".to_string()));
+        metrics.session_created(&None);
+
+        let output = metrics.encode();
+
+        for line in [
+            r#"mcp_requests_total{method="tools/call",outcome="ok"} 1"#,
+            r#"mcp_request_duration_seconds_count{method="tools/call"} 1"#,
+            r#"mcp_tool_calls_total{agent_type="counter",outcome="tool_error",tool="increment"} 1"#,
+            r#"mcp_tool_call_duration_seconds_count{agent_type="counter",tool="increment"} 1"#,
+            r#"mcp_rate_limited_total{scope="session"} 1"#,
+            r#"mcp_sessions_created_total{agent_type="counter"} 1"#,
+            r#"mcp_sessions_created_total{agent_type="global"} 1"#,
+        ] {
+            assert!(
+                output.lines().any(|l| l == line),
+                "no `{}` in\n{}",
+                line,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn gauges_go_back_down_with_their_guards() {
+        let metrics = McpMetrics::default();
+
+        let pending = metrics.track_invocation(&"counter".to_string());
+        let running = metrics.track_running_invocation();
+        assert!(metrics
+            .encode()
+            .lines()
+            .any(|l| l == r#"mcp_invocation_queue_depth{agent_type="counter"} 1"#));
+        assert!(metrics
+            .encode()
+            .lines()
+            .any(|l| l == "mcp_invocations_running 1"));
+
+        drop(pending);
+        drop(running);
+        assert!(metrics
+            .encode()
+            .lines()
+            .any(|l| l == r#"mcp_invocation_queue_depth{agent_type="counter"} 0"#));
+        assert!(metrics
+            .encode()
+            .lines()
+            .any(|l| l == "mcp_invocations_running 0"));
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+use rmcp::model::{ProgressNotificationParam, ProgressToken};
+use rmcp::{Peer, RoleServer};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::golem::{AgentProgress, ProgressReporter};
+
+// Chatty agents report progress far more often than any client cares about
+pub const PROGRESS_NOTIFICATION_INTERVAL: Duration = Duration::from_millis(100);
+
+// Forwards the progress reported by the invoker backend as `notifications/progress`,
+// at most once per interval, coalescing into the latest update in between
+pub struct McpProgress {
+    reporter: ProgressReporter,
+    done: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl McpProgress {
+    pub fn start(peer: Peer<RoleServer>, progress_token: ProgressToken, interval: Duration) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<AgentProgress>();
+        let (done, mut done_receiver) = oneshot::channel::<()>();
+
+        let task = tokio::spawn(async move {
+            let mut last_sent: Option<Instant> = None;
+            let mut pending: Option<AgentProgress> = None;
+
+            loop {
+                let deadline = last_sent.map(|sent| sent + interval).unwrap_or_else(Instant::now);
+
+                tokio::select! {
+                    update = receiver.recv() => match update {
+                        Some(update) => pending = Some(update),
+                        None => break,
+                    },
+                    _ = tokio::time::sleep_until(deadline), if pending.is_some() => {
+                        if let Some(update) = pending.take() {
+                            send_progress(&peer, &progress_token, update).await;
+                            last_sent = Some(Instant::now());
+                        }
+                    },
+                    _ = &mut done_receiver => break,
+                }
+            }
+
+            // Whatever is left over must go out before the response does
+            while let Ok(update) = receiver.try_recv() {
+                pending = Some(update);
+            }
+
+            if let Some(update) = pending {
+                send_progress(&peer, &progress_token, update).await;
+            }
+        });
+
+        Self {
+            reporter: ProgressReporter::new(sender),
+            done,
+            task,
+        }
+    }
+
+    pub fn reporter(&self) -> ProgressReporter {
+        self.reporter.clone()
+    }
+
+    // The backend may hold on to a clone of the reporter, so we can't rely on the channel closing
+    pub async fn finish(self) {
+        let _ = self.done.send(());
+        let _ = self.task.await;
+    }
+}
+
+async fn send_progress(peer: &Peer<RoleServer>, progress_token: &ProgressToken, update: AgentProgress) {
+    let result = peer
+        .notify_progress(ProgressNotificationParam {
+            progress_token: progress_token.clone(),
+            progress: update.progress,
+            total: update.total,
+            message: update.message,
+        })
+        .await;
+
+    if let Err(error) = result {
+        tracing::warn!(%error, "failed to send progress notification");
+    }
+}
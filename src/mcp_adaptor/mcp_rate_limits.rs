@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use crate::golem::AgentId;
+use crate::mcp_adaptor::McpSessionId;
+
+// Buckets are dropped once there are this many, as long as they are full again (which is the same as a new one).
+// Whatever is left after that may double before the next go, so that pruning stays cheap per call
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+// A token bucket: `burst` calls at once, refilled at `per_second`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.burst == 0 || !self.per_second.is_finite() || self.per_second <= 0.0 {
+            return Err("needs a positive burst and per_second".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+// Limits on tool calls, a call has to fit in every one that applies to it
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpRateLimits {
+    // Only applies with authentication
+    pub per_principal: Option<RateLimit>,
+    pub per_session: Option<RateLimit>,
+    pub per_agent: Option<RateLimit>,
+    // Across every client and agent the tool calls
+    pub per_tool: Option<RateLimit>,
+}
+
+impl McpRateLimits {
+    pub fn get_tool_call_keys(
+        &self,
+        principal: Option<&str>,
+        session_id: Option<&McpSessionId>,
+        agent_id: Option<&AgentId>,
+        tool_name: &str,
+    ) -> Vec<(RateLimitKey, RateLimit)> {
+        let keys = [
+            (RateLimitScope::Principal, principal.map(ToString::to_string), self.per_principal),
+            (RateLimitScope::Session, session_id.cloned(), self.per_session),
+            (RateLimitScope::Agent, agent_id.cloned(), self.per_agent),
+            (RateLimitScope::Tool, Some(tool_name.to_string()), self.per_tool),
+        ];
+
+        keys.into_iter()
+            .filter_map(|(scope, key, limit)| Some((RateLimitKey { scope, key: key? }, limit?)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitScope {
+    Principal,
+    Session,
+    Agent,
+    Tool,
+    // Requests to the mcp endpoint, by principal or client address
+    Http,
+}
+
+impl RateLimitScope {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            RateLimitScope::Principal => "principal",
+            RateLimitScope::Session => "session",
+            RateLimitScope::Agent => "agent",
+            RateLimitScope::Tool => "tool",
+            RateLimitScope::Http => "http",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RateLimitKey {
+    pub scope: RateLimitScope,
+    pub key: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitExceeded {
+    pub scope: RateLimitScope,
+    pub retry_after: Duration,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        self.updated_at = now;
+    }
+}
+
+struct TokenBuckets {
+    buckets: HashMap<RateLimitKey, (TokenBucket, RateLimit)>,
+    prune_above: usize,
+}
+
+impl TokenBuckets {
+    fn prune(&mut self, now: Instant) {
+        self.buckets.retain(|_, (bucket, limit)| {
+            bucket.refill(limit, now);
+            bucket.tokens < limit.burst as f64
+        });
+        self.prune_above = MAX_IDLE_BUCKETS.max(self.buckets.len() * 2);
+    }
+}
+
+impl Default for TokenBuckets {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            prune_above: MAX_IDLE_BUCKETS,
+        }
+    }
+}
+
+// Token buckets by key, shared by everything with the same limiter
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<TokenBuckets>>,
+}
+
+impl RateLimiter {
+    // Takes a token from each of the buckets, or from none of them if any is empty. The scope that turned the call
+    // down is the one to wait for the longest
+    pub fn try_acquire(&self, keys: &[(RateLimitKey, RateLimit)]) -> Result<(), RateLimitExceeded> {
+        self.try_acquire_at(keys, Instant::now())
+    }
+
+    fn try_acquire_at(&self, keys: &[(RateLimitKey, RateLimit)], now: Instant) -> Result<(), RateLimitExceeded> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.buckets.len() > buckets.prune_above {
+            buckets.prune(now);
+        }
+
+        let buckets = &mut buckets.buckets;
+
+        let mut exceeded: Option<RateLimitExceeded> = None;
+
+        for (key, limit) in keys {
+            let (bucket, bucket_limit) = buckets.entry(key.clone()).or_insert((
+                TokenBucket {
+                    tokens: limit.burst as f64,
+                    updated_at: now,
+                },
+                *limit,
+            ));
+
+            // The limits may have been changed since the bucket was made
+            *bucket_limit = *limit;
+            bucket.refill(limit, now);
+
+            if bucket.tokens < 1.0 {
+                let retry_after = Duration::from_secs_f64((1.0 - bucket.tokens) / limit.per_second);
+
+                if exceeded.as_ref().is_none_or(|exceeded| exceeded.retry_after < retry_after) {
+                    exceeded = Some(RateLimitExceeded {
+                        scope: key.scope,
+                        retry_after,
+                    });
+                }
+            }
+        }
+
+        if let Some(exceeded) = exceeded {
+            return Err(exceeded);
+        }
+
+        for (key, _) in keys {
+            if let Some((bucket, _)) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        burst: 3,
+        per_second: 2.0,
+    };
+
+    fn key(scope: RateLimitScope, key: &str) -> RateLimitKey {
+        RateLimitKey {
+            scope,
+            key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn burst_is_allowed_at_once_and_then_turned_down() {
+        let limiter = RateLimiter::default();
+        let keys = [(key(RateLimitScope::Tool, "counter__increment"), LIMIT)];
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire_at(&keys, now), Ok(()));
+        }
+
+        // A token comes back every half a second
+        assert_eq!(
+            limiter.try_acquire_at(&keys, now),
+            Err(RateLimitExceeded {
+                scope: RateLimitScope::Tool,
+                retry_after: Duration::from_millis(500),
+            })
+        );
+    }
+
+    #[test]
+    fn tokens_are_refilled_up_to_the_burst() {
+        let limiter = RateLimiter::default();
+        let keys = [(key(RateLimitScope::Session, "session-1"), LIMIT)];
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            limiter.try_acquire_at(&keys, now).unwrap();
+        }
+
+        let later = now + Duration::from_millis(500);
+        assert_eq!(limiter.try_acquire_at(&keys, later), Ok(()));
+        assert!(limiter.try_acquire_at(&keys, later).is_err());
+
+        // However long the wait, no more than the burst
+        let much_later = later + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire_at(&keys, much_later), Ok(()));
+        }
+        assert!(limiter.try_acquire_at(&keys, much_later).is_err());
+    }
+
+    #[test]
+    fn a_call_turned_down_takes_no_token_from_the_other_buckets() {
+        let limiter = RateLimiter::default();
+        let session = (key(RateLimitScope::Session, "session-1"), LIMIT);
+        let agent = (
+            key(RateLimitScope::Agent, "counter(1)"),
+            RateLimit {
+                burst: 1,
+                per_second: 0.5,
+            },
+        );
+        let now = Instant::now();
+
+        limiter.try_acquire_at(&[session.clone(), agent.clone()], now).unwrap();
+
+        // The agent's bucket is empty, the one to wait the longest for
+        let exceeded = limiter.try_acquire_at(&[session.clone(), agent], now).unwrap_err();
+        assert_eq!(exceeded.scope, RateLimitScope::Agent);
+        assert_eq!(exceeded.retry_after, Duration::from_secs(2));
+
+        // Two tokens are still left in the session's bucket
+        let session = [session];
+        assert_eq!(limiter.try_acquire_at(&session, now), Ok(()));
+        assert_eq!(limiter.try_acquire_at(&session, now), Ok(()));
+        assert!(limiter.try_acquire_at(&session, now).is_err());
+    }
+
+    #[test]
+    fn keys_are_made_for_the_limits_that_apply() {
+        let limits = McpRateLimits {
+            per_principal: Some(LIMIT),
+            per_session: Some(LIMIT),
+            per_agent: None,
+            per_tool: Some(LIMIT),
+        };
+        let session_id = "session-1".to_string();
+
+        let scopes = limits
+            .get_tool_call_keys(None, Some(&session_id), Some(&"counter(1)".to_string()), "increment")
+            .into_iter()
+            .map(|(key, _)| key.scope)
+            .collect::<Vec<_>>();
+
+        assert_eq!(scopes, vec![RateLimitScope::Session, RateLimitScope::Tool]);
+    }
+
+    #[test]
+    fn buckets_in_use_put_off_the_next_prune() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        // Partly drained buckets are kept, so the next prune waits until there are twice as many
+        for i in 0..=MAX_IDLE_BUCKETS {
+            limiter.try_acquire_at(&[(key(RateLimitScope::Session, &i.to_string()), LIMIT)], now).unwrap();
+        }
+        limiter.try_acquire_at(&[(key(RateLimitScope::Session, "one more"), LIMIT)], now).unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.buckets.len(), MAX_IDLE_BUCKETS + 2);
+        assert_eq!(buckets.prune_above, (MAX_IDLE_BUCKETS + 1) * 2);
+    }
+
+    #[test]
+    fn full_buckets_are_pruned() {
+        let limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        for i in 0..=MAX_IDLE_BUCKETS {
+            limiter.try_acquire_at(&[(key(RateLimitScope::Session, &i.to_string()), LIMIT)], now).unwrap();
+        }
+
+        // Refilled by now, all but the bucket just used are as good as new
+        let later = now + Duration::from_secs(10);
+        limiter.try_acquire_at(&[(key(RateLimitScope::Session, "one more"), LIMIT)], later).unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.buckets.len(), 1);
+        assert_eq!(buckets.prune_above, MAX_IDLE_BUCKETS);
+    }
+}
@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use rmcp::model::{
+    CreateMessageRequestParams, ModelHint, ModelPreferences, Role, SamplingMessage, SamplingMessageContent,
+};
+use rmcp::{Peer, RoleServer};
+use std::sync::Arc;
+
+use crate::golem::{
+    AgentModelPreferences, AgentSampler, AgentSamplingMessage, AgentSamplingRequest, AgentSamplingResponse,
+    AgentSamplingRole, SamplingRequester,
+};
+
+// Sends the agent's completion request to the client as `sampling/createMessage`
+pub struct McpSampler {
+    peer: Peer<RoleServer>,
+}
+
+impl McpSampler {
+    // Only clients that advertised sampling during initialization get asked
+    pub fn requester(peer: Peer<RoleServer>) -> SamplingRequester {
+        let supports_sampling = peer
+            .peer_info()
+            .is_some_and(|client_info| client_info.capabilities.sampling.is_some());
+
+        if supports_sampling {
+            SamplingRequester::new(Arc::new(McpSampler { peer }))
+        } else {
+            SamplingRequester::default()
+        }
+    }
+}
+
+#[async_trait]
+impl AgentSampler for McpSampler {
+    async fn sample(&self, request: AgentSamplingRequest) -> Result<AgentSamplingResponse, String> {
+        let params = CreateMessageRequestParams {
+            meta: None,
+            task: None,
+            messages: request.messages.into_iter().map(get_sampling_message).collect(),
+            model_preferences: request.model_preferences.map(get_model_preferences),
+            system_prompt: request.system_prompt,
+            include_context: None,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            stop_sequences: None,
+            metadata: None,
+            tools: None,
+            tool_choice: None,
+        };
+
+        let result = self
+            .peer
+            .create_message(params)
+            .await
+            .map_err(|e| format!("sampling failed: {}", e))?;
+
+        // Agents only deal with text for now
+        let text = result
+            .message
+            .content
+            .into_vec()
+            .into_iter()
+            .map(|content| match content {
+                SamplingMessageContent::Text(text) => Ok(text.text),
+                _ => Err("sampling returned non-text content".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n");
+
+        Ok(AgentSamplingResponse {
+            model: result.model,
+            stop_reason: result.stop_reason,
+            text,
+        })
+    }
+}
+
+fn get_sampling_message(message: AgentSamplingMessage) -> SamplingMessage {
+    let role = match message.role {
+        AgentSamplingRole::User => Role::User,
+        AgentSamplingRole::Assistant => Role::Assistant,
+    };
+
+    SamplingMessage::new(role, SamplingMessageContent::text(message.text))
+}
+
+fn get_model_preferences(preferences: AgentModelPreferences) -> ModelPreferences {
+    ModelPreferences {
+        hints: (!preferences.hints.is_empty()).then(|| {
+            preferences
+                .hints
+                .into_iter()
+                .map(|name| ModelHint { name: Some(name) })
+                .collect()
+        }),
+        cost_priority: preferences.cost_priority,
+        speed_priority: preferences.speed_priority,
+        intelligence_priority: preferences.intelligence_priority,
+    }
+}
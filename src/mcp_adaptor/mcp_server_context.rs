@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::Duration;
+use rmcp::task_manager::OperationProcessor;
+use tokio::sync::Mutex;
+use crate::golem::{AgentEventSource, AgentEvents, AgentInvoker, AgentRegistry, DummyAgentInvoker};
+use crate::mcp_adaptor::{AuditSink, DEFAULT_TASK_TTL, InMemoryMcpTaskStore, McpInvocationLimits, McpInvocationQueue, McpLoggers, McpMappingRules, McpMetrics, McpRateLimits, RateLimiter, McpSessions, McpTaskStore};
+
+// Everything shared between the per-session `GolemAgentMcpServer` instances
+#[derive(Clone)]
+pub struct McpServerContext {
+    pub registry: AgentRegistry,
+    pub invoker: Arc<dyn AgentInvoker>,
+    pub agent_events: Arc<dyn AgentEventSource>,
+    pub sessions: McpSessions,
+    // Runs the task-augmented tool calls. Shared rather than per session,
+    // so that a task can be cancelled after a reconnect
+    pub processor: Arc<Mutex<OperationProcessor>>,
+    pub tasks: Arc<dyn McpTaskStore>,
+    // How long tasks are kept from when they are created, finished or not
+    pub task_ttl: Duration,
+    // Install `McpLoggingLayer::new(loggers.clone())` in the tracing subscriber for `logging/setLevel` to have any effect
+    pub loggers: McpLoggers,
+    // Which agent methods become tools, resources or prompts
+    pub mapping_rules: McpMappingRules,
+    // Whether every agent type also gets `create_agent`, `list_agents`, `get_agent_status` and `delete_agent` tools
+    pub management_tools: bool,
+    // Scraped off `/metrics` by the server binary
+    pub metrics: McpMetrics,
+    // Every agent method call is recorded here, if set
+    pub audit: Option<Arc<dyn AuditSink>>,
+    // Tool calls over these limits are turned down with a tool error
+    pub rate_limits: McpRateLimits,
+    pub rate_limiter: RateLimiter,
+    // Every agent invocation goes through it, to wait for its turn
+    pub invocations: McpInvocationQueue,
+}
+
+impl Default for McpServerContext {
+    fn default() -> Self {
+        Self::new(
+            AgentRegistry::default(),
+            Arc::new(DummyAgentInvoker),
+            Arc::new(AgentEvents::default()),
+        )
+    }
+}
+
+impl McpServerContext {
+    pub fn new(
+        registry: AgentRegistry,
+        invoker: Arc<dyn AgentInvoker>,
+        agent_events: Arc<dyn AgentEventSource>,
+    ) -> Self {
+        let metrics = McpMetrics::default();
+
+        Self {
+            registry,
+            invoker,
+            agent_events,
+            sessions: McpSessions::default(),
+            processor: Arc::new(Mutex::new(OperationProcessor::new())),
+            tasks: Arc::new(InMemoryMcpTaskStore::default()),
+            task_ttl: DEFAULT_TASK_TTL,
+            loggers: McpLoggers::default(),
+            mapping_rules: McpMappingRules::default(),
+            management_tools: false,
+            invocations: McpInvocationQueue::new(McpInvocationLimits::default(), metrics.clone()),
+            metrics,
+            audit: None,
+            rate_limits: McpRateLimits::default(),
+            rate_limiter: RateLimiter::default(),
+        }
+    }
+
+    pub fn with_mapping_rules(mut self, mapping_rules: McpMappingRules) -> Self {
+        self.mapping_rules = mapping_rules;
+        self
+    }
+
+    pub fn with_management_tools(mut self, enabled: bool) -> Self {
+        self.management_tools = enabled;
+        self
+    }
+
+    pub fn with_audit(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn with_rate_limits(mut self, rate_limits: McpRateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    pub fn with_task_ttl(mut self, task_ttl: Duration) -> Self {
+        self.task_ttl = task_ttl;
+        self
+    }
+
+    pub fn with_invocation_limits(mut self, limits: McpInvocationLimits) -> Self {
+        self.invocations = McpInvocationQueue::new(limits, self.metrics.clone());
+        self
+    }
+}
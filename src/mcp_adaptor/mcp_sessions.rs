@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use rmcp::model::{Extensions, ServerNotification};
+use rmcp::transport::common::http_header::HEADER_SESSION_ID;
+use rmcp::{Peer, RoleServer};
+use tokio::sync::RwLock;
+
+use crate::golem::AgentId;
+
+pub type McpSessionId = String;
+
+// A live MCP session. The peer is the only handle through which we can push
+// anything to the client outside of a request (it ends up in the standalone `GET /mcp` stream)
+#[derive(Clone)]
+pub struct McpSession {
+    pub session_id: McpSessionId,
+    pub agent_id: Option<AgentId>,
+    pub peer: Peer<RoleServer>,
+}
+
+#[derive(Clone, Debug)]
+pub enum NotificationTarget {
+    Session(McpSessionId),
+    Agent(AgentId),
+    All,
+}
+
+// Shared across every `GolemAgentMcpServer` created by the service factory,
+// given rmcp creates one server instance per session
+#[derive(Clone, Default)]
+pub struct McpSessions {
+    sessions: Arc<RwLock<HashMap<McpSessionId, McpSession>>>,
+}
+
+impl McpSessions {
+    pub async fn register(&self, session: McpSession) {
+        // The agent id is on the request span already, with its sensitive params redacted
+        tracing::info!(session_id = %session.session_id, "mcp session registered");
+        self.sessions
+            .write()
+            .await
+            .insert(session.session_id.clone(), session);
+    }
+
+    pub async fn remove(&self, session_id: &str) -> Option<McpSession> {
+        self.sessions.write().await.remove(session_id)
+    }
+
+    pub async fn get(&self, session_id: &str) -> Option<McpSession> {
+        self.sessions.read().await.get(session_id).cloned()
+    }
+
+    pub async fn list(&self, target: &NotificationTarget) -> Vec<McpSession> {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .filter(|session| match target {
+                NotificationTarget::Session(session_id) => &session.session_id == session_id,
+                NotificationTarget::Agent(agent_id) => session.agent_id.as_ref() == Some(agent_id),
+                NotificationTarget::All => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Live sessions per agent, the global server's sessions being under None
+    pub async fn count_by_agent(&self) -> BTreeMap<Option<AgentId>, usize> {
+        let mut counts = BTreeMap::new();
+
+        for session in self.sessions.read().await.values() {
+            if !session.peer.is_transport_closed() {
+                *counts.entry(session.agent_id.clone()).or_default() += 1;
+            }
+        }
+
+        counts
+    }
+
+    // Returns the number of sessions the notification was delivered to.
+    // Sessions whose transport is gone are dropped on the way.
+    pub async fn notify(&self, target: NotificationTarget, notification: ServerNotification) -> usize {
+        let mut delivered = 0;
+
+        for session in self.list(&target).await {
+            if session.peer.is_transport_closed() {
+                self.remove(&session.session_id).await;
+                continue;
+            }
+
+            match session.peer.send_notification(notification.clone()).await {
+                Ok(()) => delivered += 1,
+                Err(error) => {
+                    tracing::warn!(session_id = %session.session_id, %error, "failed to notify mcp session");
+                    self.remove(&session.session_id).await;
+                }
+            }
+        }
+
+        delivered
+    }
+}
+
+// The `notifications/initialized` notification is the first message that carries the
+// `Mcp-Session-Id` header (initialize itself is what creates the session)
+pub fn get_session_id(extensions: &Extensions) -> Option<McpSessionId> {
+    extensions
+        .get::<http::request::Parts>()
+        .and_then(|parts| parts.headers.get(HEADER_SESSION_ID))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rmcp::model::{CallToolResult, Extensions, Task, TaskStatus};
+use rmcp::task_manager::current_timestamp;
+use rmcp::ErrorData;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::golem::AgentId;
+use crate::mcp_adaptor::{get_principal, get_session_id};
+
+// How long a task (and its result) is kept from when it was created, unless the context says otherwise
+pub const DEFAULT_TASK_TTL: Duration = Duration::from_secs(60 * 60);
+
+// Who may see a task. The principal when there is one, so that the client can reconnect and still fetch
+// the result, otherwise only the session that created it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpTaskOwner {
+    Principal(String),
+    Session(String),
+}
+
+impl McpTaskOwner {
+    // None for a stateless server without auth, whose tasks are only known by their (random) ids
+    pub fn from(extensions: &Extensions) -> Option<Self> {
+        match get_principal(extensions) {
+            Some(principal) => Some(Self::Principal(principal.0)),
+            None => get_session_id(extensions).map(Self::Session),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum McpTaskOutcome {
+    Completed(CallToolResult),
+    Failed(ErrorData),
+}
+
+// Everything needed to answer tasks/get and tasks/result, from any session of the owner
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct McpTaskRecord {
+    pub task: Task,
+    pub agent_id: Option<AgentId>,
+    pub owner: Option<McpTaskOwner>,
+    pub tool_name: String,
+    pub outcome: Option<McpTaskOutcome>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl McpTaskRecord {
+    pub fn new(task_id: String, agent_id: Option<AgentId>, owner: Option<McpTaskOwner>, tool_name: String, ttl: Duration) -> Self {
+        let timestamp = current_timestamp();
+
+        Self {
+            task: Task {
+                task_id,
+                status: TaskStatus::Working,
+                status_message: Some("Task accepted".to_string()),
+                created_at: timestamp.clone(),
+                last_updated_at: timestamp,
+                ttl: Some(ttl.as_millis() as u64),
+                poll_interval: None,
+            },
+            agent_id,
+            owner,
+            tool_name,
+            outcome: None,
+            expires_at: Utc::now() + ttl,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.task.status,
+            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+        )
+    }
+
+    pub fn finish(&mut self, outcome: McpTaskOutcome) {
+        self.task.status = match &outcome {
+            McpTaskOutcome::Completed(result) if result.is_error == Some(true) => TaskStatus::Failed,
+            McpTaskOutcome::Completed(_) => TaskStatus::Completed,
+            McpTaskOutcome::Failed(_) => TaskStatus::Failed,
+        };
+        self.task.status_message = None;
+        self.task.last_updated_at = current_timestamp();
+        self.outcome = Some(outcome);
+    }
+
+    pub fn cancel(&mut self) {
+        self.task.status = TaskStatus::Cancelled;
+        self.task.status_message = Some("Task cancelled".to_string());
+        self.task.last_updated_at = current_timestamp();
+    }
+}
+
+// Task state outlives the session that created the task (rmcp's OperationProcessor keeps it per server,
+// i.e per session), so that a client can reconnect and still fetch the result.
+// Expired tasks are as good as gone, whether or not the store got to remove them yet
+#[async_trait]
+pub trait McpTaskStore: Send + Sync {
+    async fn put(&self, record: McpTaskRecord) -> Result<(), String>;
+
+    async fn get(&self, task_id: &str) -> Result<Option<McpTaskRecord>, String>;
+
+    // Tasks of the owner, of the given agent or of all agents if there is no agent.
+    // Ownerless tasks are never listed, they are only known by their ids
+    async fn list(&self, agent_id: Option<&AgentId>, owner: &McpTaskOwner) -> Result<Vec<McpTaskRecord>, String>;
+}
+
+// Expired tasks are removed whenever a task is stored, there is no need for a task of its own doing that
+#[derive(Clone, Default)]
+pub struct InMemoryMcpTaskStore {
+    records: Arc<RwLock<HashMap<String, McpTaskRecord>>>,
+}
+
+#[async_trait]
+impl McpTaskStore for InMemoryMcpTaskStore {
+    async fn put(&self, record: McpTaskRecord) -> Result<(), String> {
+        let mut records = self.records.write().await;
+        records.insert(record.task.task_id.clone(), record);
+        records.retain(|_, record| !record.is_expired());
+        Ok(())
+    }
+
+    async fn get(&self, task_id: &str) -> Result<Option<McpTaskRecord>, String> {
+        Ok(self
+            .records
+            .read()
+            .await
+            .get(task_id)
+            .filter(|record| !record.is_expired())
+            .cloned())
+    }
+
+    async fn list(&self, agent_id: Option<&AgentId>, owner: &McpTaskOwner) -> Result<Vec<McpTaskRecord>, String> {
+        let mut records = self
+            .records
+            .read()
+            .await
+            .values()
+            .filter(|record| agent_id.is_none() || record.agent_id.as_ref() == agent_id)
+            .filter(|record| record.owner.as_ref() == Some(owner) && !record.is_expired())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        records.sort_by(|a, b| a.task.created_at.cmp(&b.task.created_at));
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::transport::common::http_header::HEADER_SESSION_ID;
+
+    use crate::mcp_adaptor::AuthPrincipal;
+
+    use super::*;
+
+    fn record(task_id: &str, owner: Option<McpTaskOwner>, ttl: Duration) -> McpTaskRecord {
+        McpTaskRecord::new(task_id.to_string(), None, owner, "counter__increment".to_string(), ttl)
+    }
+
+    fn session(session_id: &str) -> Option<McpTaskOwner> {
+        Some(McpTaskOwner::Session(session_id.to_string()))
+    }
+
+    fn extensions(principal: Option<&str>, session_id: Option<&str>) -> Extensions {
+        let mut request = http::Request::builder();
+        if let Some(session_id) = session_id {
+            request = request.header(HEADER_SESSION_ID, session_id);
+        }
+
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        if let Some(principal) = principal {
+            parts.extensions.insert(AuthPrincipal(principal.to_string()));
+        }
+
+        let mut extensions = Extensions::new();
+        extensions.insert(parts);
+        extensions
+    }
+
+    #[test]
+    fn tasks_belong_to_the_principal_over_the_session() {
+        assert_eq!(
+            McpTaskOwner::from(&extensions(Some("ann"), Some("session-1"))),
+            Some(McpTaskOwner::Principal("ann".to_string()))
+        );
+        assert_eq!(McpTaskOwner::from(&extensions(None, Some("session-1"))), session("session-1"));
+        assert_eq!(McpTaskOwner::from(&extensions(None, None)), None);
+    }
+
+    #[test]
+    fn ttl_is_advertised_on_the_task() {
+        let record = record("task-1", None, Duration::from_secs(60));
+        assert_eq!(record.task.ttl, Some(60_000));
+    }
+
+    #[tokio::test]
+    async fn tasks_are_only_listed_for_their_owner() {
+        let store = InMemoryMcpTaskStore::default();
+        store.put(record("task-1", session("session-1"), DEFAULT_TASK_TTL)).await.unwrap();
+        store.put(record("task-2", session("session-2"), DEFAULT_TASK_TTL)).await.unwrap();
+        store.put(record("task-3", None, DEFAULT_TASK_TTL)).await.unwrap();
+
+        let task_ids = |records: Vec<McpTaskRecord>| records.into_iter().map(|record| record.task.task_id).collect::<Vec<_>>();
+
+        assert_eq!(task_ids(store.list(None, &session("session-1").unwrap()).await.unwrap()), vec!["task-1"]);
+        assert_eq!(task_ids(store.list(None, &session("session-3").unwrap()).await.unwrap()), Vec::<String>::new());
+
+        // Ownerless tasks are only found by id
+        assert!(store.get("task-3").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn expired_tasks_are_gone() {
+        let store = InMemoryMcpTaskStore::default();
+        store.put(record("task-1", session("session-1"), Duration::from_millis(20))).await.unwrap();
+        assert!(store.get("task-1").await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(store.get("task-1").await.unwrap().is_none());
+        assert!(store.list(None, &session("session-1").unwrap()).await.unwrap().is_empty());
+
+        // And removed for good with the next task stored
+        store.put(record("task-2", None, DEFAULT_TASK_TTL)).await.unwrap();
+        assert_eq!(store.records.read().await.keys().collect::<Vec<_>>(), vec!["task-2"]);
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use crate::golem::{get_agent_type, AgentId, AgentType};
+use crate::mcp_adaptor::agent_mcp_prompt::AgentMcpPrompt;
+use crate::mcp_adaptor::{
+    get_agent_capabilities, get_management_tools, AgentMcpTool, McpAgentCapability,
+    McpCapabilityKind, McpServerContext, AGENT_ARGUMENT,
+};
+
+// The global server prefixes every tool and prompt with its agent type, e.g `counter__increment`
+pub const TOOL_NAME_SEPARATOR: &str = "__";
+
+// Newer revisions of the spec allow 128, but plenty of clients (and llm apis) still reject anything over 64
+pub const MAX_TOOL_NAME_LENGTH: usize = 64;
+
+// The bare method name for an agent's own server, namespaced by the agent type for the global server
+pub fn get_tool_name(agent_type: &AgentType, method_name: &str, namespaced: bool) -> String {
+    if namespaced {
+        format!(
+            "{}{}{}",
+            get_mcp_name(agent_type),
+            TOOL_NAME_SEPARATOR,
+            get_mcp_name(method_name)
+        )
+    } else {
+        get_mcp_name(method_name)
+    }
+}
+
+// Prompts go by the same rules as tools, as they share a namespace in the global server just as much,
+// e.g `counter__get_increment_prompt`
+pub fn get_prompt_name(agent_type: &AgentType, method_name: &str, namespaced: bool) -> String {
+    get_tool_name(
+        agent_type,
+        &format!("get_{}_prompt", method_name),
+        namespaced,
+    )
+}
+
+// Tool names are restricted to `[A-Za-z0-9_-]`, anything else (agent types like `counter(1)`) becomes `_`.
+// This is lossy, hence the collision check below
+pub fn get_mcp_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum McpToolNameError {
+    // More than one method (agent type, method name) ended up with the same tool (or prompt) name
+    Collision {
+        kind: McpCapabilityKind,
+        tool_name: String,
+        methods: Vec<(AgentType, String)>,
+    },
+    Invalid {
+        kind: McpCapabilityKind,
+        tool_name: String,
+        agent_type: AgentType,
+        method_name: String,
+    },
+    // A method parameter with the name of the argument the global server adds, see `AGENT_ARGUMENT`
+    ReservedParameter {
+        tool_name: String,
+        agent_type: AgentType,
+        method_name: String,
+    },
+}
+
+impl Display for McpToolNameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            McpToolNameError::Collision {
+                kind,
+                tool_name,
+                methods,
+            } => {
+                let methods = methods
+                    .iter()
+                    .map(|(agent_type, method_name)| {
+                        format!("`{}` of `{}`", method_name, agent_type)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(
+                    f,
+                    "{} name `{}` is used by more than one method: {}",
+                    get_kind_name(kind),
+                    tool_name,
+                    methods
+                )
+            }
+            McpToolNameError::Invalid {
+                kind,
+                tool_name,
+                agent_type,
+                method_name,
+            } => write!(
+                f,
+                "{} name `{}` of `{}` of `{}` must be between 1 and {} characters",
+                get_kind_name(kind),
+                tool_name,
+                method_name,
+                agent_type,
+                MAX_TOOL_NAME_LENGTH
+            ),
+            McpToolNameError::ReservedParameter {
+                tool_name,
+                agent_type,
+                method_name,
+            } => write!(
+                f,
+                "tool `{}` can't be added as `{}` of `{}` has a parameter named `{}`",
+                tool_name, method_name, agent_type, AGENT_ARGUMENT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for McpToolNameError {}
+
+fn get_kind_name(kind: &McpCapabilityKind) -> &'static str {
+    match kind {
+        McpCapabilityKind::Tool => "tool",
+        McpCapabilityKind::Resource => "resource",
+        McpCapabilityKind::Prompt => "prompt",
+    }
+}
+
+// Names every tool, leaving out (and reporting) the ones whose name is invalid or collides with another,
+// as there is no telling which of them a client meant
+pub fn get_named_tools(
+    tools: Vec<AgentMcpTool>,
+    namespaced: bool,
+) -> (Vec<(String, AgentMcpTool)>, Vec<McpToolNameError>) {
+    let mut errors = vec![];
+    let mut named = vec![];
+
+    for tool in tools {
+        let tool_name = get_tool_name(&tool.agent_type, &tool.tool.method_name, namespaced);
+
+        if namespaced
+            && tool
+                .tool
+                .input_schema
+                .iter()
+                .any(|entry| entry.name == AGENT_ARGUMENT)
+        {
+            errors.push(McpToolNameError::ReservedParameter {
+                tool_name,
+                agent_type: tool.agent_type.clone(),
+                method_name: tool.tool.method_name.clone(),
+            });
+        } else {
+            let method = (tool.agent_type.clone(), tool.tool.method_name.clone());
+            named.push((tool_name, method, tool));
+        }
+    }
+
+    let (named_tools, other_errors) = get_unique_names(McpCapabilityKind::Tool, named);
+    errors.extend(other_errors);
+
+    (named_tools, errors)
+}
+
+// Same as `get_named_tools`, for prompts
+pub fn get_named_prompts(
+    prompts: Vec<AgentMcpPrompt>,
+    namespaced: bool,
+) -> (Vec<(String, AgentMcpPrompt)>, Vec<McpToolNameError>) {
+    let named = prompts
+        .into_iter()
+        .map(|prompt| {
+            let prompt_name = get_prompt_name(
+                &prompt.agent_type,
+                &prompt.agent_method.method_name,
+                namespaced,
+            );
+            let method = (
+                prompt.agent_type.clone(),
+                prompt.agent_method.method_name.clone(),
+            );
+            (prompt_name, method, prompt)
+        })
+        .collect();
+
+    get_unique_names(McpCapabilityKind::Prompt, named)
+}
+
+// The agent type and method name a tool or prompt is for
+type MethodRef = (AgentType, String);
+
+fn get_unique_names<T>(
+    kind: McpCapabilityKind,
+    named: Vec<(String, MethodRef, T)>,
+) -> (Vec<(String, T)>, Vec<McpToolNameError>) {
+    let mut by_name: BTreeMap<String, Vec<(MethodRef, T)>> = BTreeMap::new();
+    let mut errors = vec![];
+
+    for (name, (agent_type, method_name), item) in named {
+        if name.is_empty() || name.len() > MAX_TOOL_NAME_LENGTH {
+            errors.push(McpToolNameError::Invalid {
+                kind,
+                tool_name: name,
+                agent_type,
+                method_name,
+            });
+        } else {
+            by_name
+                .entry(name)
+                .or_default()
+                .push(((agent_type, method_name), item));
+        }
+    }
+
+    let mut unique = vec![];
+
+    for (name, mut items) in by_name {
+        if items.len() == 1 {
+            unique.push((name, items.remove(0).1));
+        } else {
+            errors.push(McpToolNameError::Collision {
+                kind,
+                tool_name: name,
+                methods: items.into_iter().map(|(method, _)| method).collect(),
+            });
+        }
+    }
+
+    (unique, errors)
+}
+
+// In the global server, tools also take the agent to call as an argument
+pub fn get_agent_tools(agent_id: Option<AgentId>, context: &McpServerContext) -> Vec<AgentMcpTool> {
+    let global = agent_id.is_none();
+
+    let mut tools: Vec<AgentMcpTool> = get_agent_capabilities(agent_id.clone(), context)
+        .into_iter()
+        .filter_map(|capability| match capability {
+            McpAgentCapability::Tool(agent_mcp_tool) if global => {
+                let constructor_schema = context
+                    .registry
+                    .get_constructor_schema(&agent_mcp_tool.agent_type)?;
+                Some(agent_mcp_tool.with_agent_argument(constructor_schema))
+            }
+            McpAgentCapability::Tool(agent_mcp_tool) => Some(agent_mcp_tool),
+            McpAgentCapability::Resource(_) | McpAgentCapability::Prompt(_) => None,
+        })
+        .collect();
+
+    if context.management_tools {
+        let agent_types = match &agent_id {
+            Some(agent_id) => vec![get_agent_type(agent_id)],
+            None => context.registry.get_agent_types(),
+        };
+
+        for agent_type in agent_types {
+            if let Some(constructor_schema) = context.registry.get_constructor_schema(&agent_type) {
+                tools.extend(get_management_tools(
+                    &agent_type,
+                    global.then_some(constructor_schema),
+                ));
+            }
+        }
+    }
+
+    tools
+}
+
+pub fn get_agent_prompts(
+    agent_id: Option<AgentId>,
+    context: &McpServerContext,
+) -> Vec<AgentMcpPrompt> {
+    get_agent_capabilities(agent_id, context)
+        .into_iter()
+        .filter_map(|capability| match capability {
+            McpAgentCapability::Prompt(agent_mcp_prompt) => Some(agent_mcp_prompt),
+            McpAgentCapability::Tool(_) | McpAgentCapability::Resource(_) => None,
+        })
+        .collect()
+}
+
+// Meant to be run at startup, so that a clash fails the server rather than silently hiding tools (or prompts).
+// Checks the global server as well as each agent type's own server
+pub fn check_tool_names(context: &McpServerContext) -> Result<(), Vec<McpToolNameError>> {
+    let tools = get_agent_tools(None, context);
+    let prompts = get_agent_prompts(None, context);
+    let (_, mut errors) = get_named_tools(tools.clone(), true);
+    errors.extend(get_named_prompts(prompts.clone(), true).1);
+
+    for agent_type in context.registry.get_agent_types() {
+        let tools = tools
+            .iter()
+            .filter(|tool| tool.agent_type == agent_type)
+            .cloned()
+            .collect();
+        let prompts = prompts
+            .iter()
+            .filter(|prompt| prompt.agent_type == agent_type)
+            .cloned()
+            .collect();
+
+        errors.extend(get_named_tools(tools, false).1);
+        errors.extend(get_named_prompts(prompts, false).1);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::golem::{
+        AgentMethod, AgentMethodHints, AgentMethodTaskSupport, AgentRegistry, AgentTypeDefinition,
+        DataSchemaEntry, ElementSchema,
+    };
+    use crate::mcp_adaptor::{McpMappingRules, McpMethodPattern};
+
+    use super::*;
+
+    fn method(method_name: &str, parameter: &str) -> AgentMethod {
+        AgentMethod {
+            method_name: method_name.to_string(),
+            title: None,
+            description: None,
+            input_schema: vec![DataSchemaEntry::new(parameter, ElementSchema::U32)],
+            output_schema: vec![],
+            hints: AgentMethodHints::default(),
+            task_support: AgentMethodTaskSupport::Forbidden,
+            exposed_as: vec![],
+        }
+    }
+
+    fn tool(agent_type: &str, method: AgentMethod) -> AgentMcpTool {
+        AgentMcpTool {
+            agent_type: agent_type.to_string(),
+            tool: method,
+            constructor_schema: None,
+            management: None,
+        }
+    }
+
+    fn prompt(agent_type: &str, method: AgentMethod) -> AgentMcpPrompt {
+        AgentMcpPrompt {
+            agent_type: agent_type.to_string(),
+            agent_method: method,
+        }
+    }
+
+    // Every method is a tool and a prompt
+    fn context(agent_types: &[(&str, AgentMethod)]) -> McpServerContext {
+        let registry = AgentRegistry::new(
+            agent_types
+                .iter()
+                .map(|(agent_type, method)| {
+                    let definition = AgentTypeDefinition {
+                        constructor_schema: vec![DataSchemaEntry::new("id", ElementSchema::U32)],
+                        methods: vec![method.clone()],
+                    };
+                    (agent_type.to_string(), definition)
+                })
+                .collect(),
+        );
+
+        McpServerContext {
+            registry,
+            ..Default::default()
+        }
+        .with_mapping_rules(McpMappingRules::default().with_rule(
+            McpMethodPattern::new(None, "*"),
+            vec![McpCapabilityKind::Tool, McpCapabilityKind::Prompt],
+        ))
+    }
+
+    #[test]
+    fn names_are_namespaced_only_in_the_global_server() {
+        assert_eq!(
+            get_tool_name(&"counter".to_string(), "increment", true),
+            "counter__increment"
+        );
+        assert_eq!(
+            get_tool_name(&"counter".to_string(), "increment", false),
+            "increment"
+        );
+        assert_eq!(
+            get_prompt_name(&"counter".to_string(), "increment", true),
+            "counter__get_increment_prompt"
+        );
+        assert_eq!(
+            get_prompt_name(&"counter".to_string(), "increment", false),
+            "get_increment_prompt"
+        );
+    }
+
+    #[test]
+    fn invalid_characters_become_underscores() {
+        assert_eq!(
+            get_tool_name(&"my.counter".to_string(), "add one", true),
+            "my_counter__add_one"
+        );
+        assert_eq!(get_mcp_name("a-b_c9"), "a-b_c9");
+    }
+
+    #[test]
+    fn colliding_tools_are_left_out_and_reported() {
+        let tools = vec![
+            tool("my.counter", method("increment", "number")),
+            tool("my_counter", method("increment", "number")),
+            tool("timer", method("increment", "number")),
+        ];
+
+        let (named_tools, errors) = get_named_tools(tools, true);
+
+        let names = named_tools
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["timer__increment"]);
+        assert_eq!(
+            errors,
+            vec![McpToolNameError::Collision {
+                kind: McpCapabilityKind::Tool,
+                tool_name: "my_counter__increment".to_string(),
+                methods: vec![
+                    ("my.counter".to_string(), "increment".to_string()),
+                    ("my_counter".to_string(), "increment".to_string()),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn too_long_and_reserved_names_are_reported() {
+        let long_name = "a".repeat(MAX_TOOL_NAME_LENGTH);
+        let tools = vec![
+            tool("counter", method(&long_name, "number")),
+            tool("counter", method("increment", AGENT_ARGUMENT)),
+        ];
+
+        let (named_tools, errors) = get_named_tools(tools.clone(), true);
+        assert!(named_tools.is_empty());
+        assert!(matches!(
+            errors[0],
+            McpToolNameError::ReservedParameter { .. }
+        ));
+        assert!(matches!(
+            errors[1],
+            McpToolNameError::Invalid {
+                kind: McpCapabilityKind::Tool,
+                ..
+            }
+        ));
+
+        // The agent's own server doesn't add the agent argument, and has room for the longer name
+        let (named_tools, errors) = get_named_tools(tools, false);
+        assert_eq!(named_tools.len(), 2);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn prompts_of_different_agent_types_do_not_collide() {
+        let prompts = vec![
+            prompt("counter", method("increment", "number")),
+            prompt("timer", method("increment", "number")),
+        ];
+
+        let (named_prompts, errors) = get_named_prompts(prompts, true);
+
+        let names = named_prompts
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "counter__get_increment_prompt",
+                "timer__get_increment_prompt"
+            ]
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn check_reports_colliding_tools_and_prompts() {
+        let context_without_collisions = context(&[
+            ("counter", method("increment", "number")),
+            ("timer", method("increment", "number")),
+        ]);
+        assert_eq!(check_tool_names(&context_without_collisions), Ok(()));
+
+        let errors = check_tool_names(&context(&[
+            ("my.counter", method("increment", "number")),
+            ("my_counter", method("increment", "number")),
+        ]))
+        .unwrap_err();
+
+        let kinds = errors
+            .iter()
+            .map(|error| match error {
+                McpToolNameError::Collision { kind, .. } => *kind,
+                other => panic!("unexpected error: {}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![McpCapabilityKind::Tool, McpCapabilityKind::Prompt]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use rmcp::model::{ClientNotification, ClientRequest, Extensions, Meta, ServerInfo, ServerResult};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{ErrorData, RoleServer, Service};
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::golem::{AgentId, TraceContext};
+use crate::mcp_adaptor::get_session_id;
+
+pub const TRACEPARENT: &str = "traceparent";
+pub const TRACESTATE: &str = "tracestate";
+
+// Wraps the server (or another wrapper of it) so that every request gets a span, continuing the client's trace.
+// There is no single place in `ServerHandler` that sees them all. Spans only make it to a collector with the
+// opentelemetry layer installed, see `init_logging` of the server binary
+#[derive(Clone)]
+pub struct McpInstrumentedServer<S> {
+    inner: S,
+}
+
+impl<S> McpInstrumentedServer<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: Service<RoleServer>> Service<RoleServer> for McpInstrumentedServer<S> {
+    async fn handle_request(&self, request: ClientRequest, context: RequestContext<RoleServer>) -> Result<ServerResult, ErrorData> {
+        let method = request.method().to_string();
+
+        let tool = match &request {
+            ClientRequest::CallToolRequest(request) => Some(request.params.name.to_string()),
+            _ => None,
+        };
+
+        // The parent of `GolemAgentMcpServer::request_span`, which has the agent id
+        let span = tracing::info_span!(
+            "mcp_rpc",
+            otel.name = %method,
+            otel.kind = "server",
+            session_id = get_session_id(&context.extensions),
+            method = %method,
+            tool = tool,
+        );
+
+        if let Some(remote) = get_remote_trace_context(&context) {
+            let _ = span.set_parent(extract(&remote));
+        }
+
+        self.inner.handle_request(request, context).instrument(span).await
+    }
+
+    async fn handle_notification(&self, notification: ClientNotification, context: NotificationContext<RoleServer>) -> Result<(), ErrorData> {
+        self.inner.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        self.inner.get_info()
+    }
+}
+
+// The call to the agent, as a child of the request span
+pub fn get_invocation_span(agent_id: &AgentId, method_name: &str) -> Span {
+    tracing::info_span!("agent_invocation", otel.kind = "client", agent_id = %agent_id, method_name = %method_name)
+}
+
+// What the backend continues the trace from: the invocation span when it is exported, otherwise whatever the
+// client sent, so that the trace isn't broken just because this server doesn't report to a collector
+pub fn get_trace_context(invocation_span: &Span, context: &RequestContext<RoleServer>) -> Option<TraceContext> {
+    inject(invocation_span).or_else(|| get_remote_trace_context(context))
+}
+
+pub fn get_remote_trace_context(context: &RequestContext<RoleServer>) -> Option<TraceContext> {
+    get_request_trace_context(&context.meta, &context.extensions)
+}
+
+// `_meta` takes precedence over the http headers, as it is specific to the request
+// (while a client may well send the same headers with every request of a session)
+fn get_request_trace_context(meta: &Meta, extensions: &Extensions) -> Option<TraceContext> {
+    get_meta_trace_context(meta).or_else(|| {
+        extensions
+            .get::<http::request::Parts>()
+            .and_then(|parts| get_header_trace_context(&parts.headers))
+    })
+}
+
+fn get_meta_trace_context(meta: &Meta) -> Option<TraceContext> {
+    let traceparent = meta.get(TRACEPARENT)?.as_str()?;
+
+    Some(TraceContext {
+        traceparent: traceparent.to_string(),
+        tracestate: meta.get(TRACESTATE).and_then(|value| value.as_str()).map(|value| value.to_string()),
+    })
+    .filter(is_valid)
+}
+
+fn get_header_trace_context(headers: &http::HeaderMap) -> Option<TraceContext> {
+    let traceparent = headers.get(TRACEPARENT)?.to_str().ok()?;
+
+    Some(TraceContext {
+        traceparent: traceparent.to_string(),
+        tracestate: headers.get(TRACESTATE).and_then(|value| value.to_str().ok()).map(|value| value.to_string()),
+    })
+    .filter(is_valid)
+}
+
+// A malformed traceparent is ignored, as the spec asks, rather than starting a trace off garbage
+fn is_valid(trace_context: &TraceContext) -> bool {
+    extract(trace_context).span().span_context().is_valid()
+}
+
+fn extract(trace_context: &TraceContext) -> opentelemetry::Context {
+    TraceContextPropagator::new().extract(trace_context)
+}
+
+// Nothing without the opentelemetry layer, the span has no ids then
+fn inject(span: &Span) -> Option<TraceContext> {
+    let mut trace_context = TraceContext::default();
+    TraceContextPropagator::new().inject_context(&span.context(), &mut trace_context);
+
+    Some(trace_context).filter(|trace_context| !trace_context.traceparent.is_empty())
+}
+
+impl Extractor for TraceContext {
+    fn get(&self, key: &str) -> Option<&str> {
+        match key {
+            TRACEPARENT => Some(&self.traceparent),
+            TRACESTATE => self.tracestate.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![TRACEPARENT, TRACESTATE]
+    }
+}
+
+impl Injector for TraceContext {
+    fn set(&mut self, key: &str, value: String) {
+        match key {
+            TRACEPARENT => self.traceparent = value,
+            // An empty tracestate is the same as none
+            TRACESTATE if !value.is_empty() => self.tracestate = Some(value),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use serde_json::json;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    const CLIENT_TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    const HEADER_TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    fn get_meta(traceparent: &str, tracestate: Option<&str>) -> Meta {
+        let mut meta = Meta::new();
+        meta.insert(TRACEPARENT.to_string(), json!(traceparent));
+        if let Some(tracestate) = tracestate {
+            meta.insert(TRACESTATE.to_string(), json!(tracestate));
+        }
+        meta
+    }
+
+    fn get_extensions(traceparent: &str) -> Extensions {
+        let (parts, _) = http::Request::builder()
+            .header(TRACEPARENT, traceparent)
+            .header(TRACESTATE, "vendor=header")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        let mut extensions = Extensions::new();
+        extensions.insert(parts);
+        extensions
+    }
+
+    fn get_trace_id(traceparent: &str) -> &str {
+        traceparent.split('-').nth(1).unwrap()
+    }
+
+    #[test]
+    fn traceparent_is_taken_from_the_headers() {
+        let trace_context = get_request_trace_context(&Meta::new(), &get_extensions(HEADER_TRACEPARENT));
+
+        assert_eq!(
+            trace_context,
+            Some(TraceContext {
+                traceparent: HEADER_TRACEPARENT.to_string(),
+                tracestate: Some("vendor=header".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn meta_takes_precedence_over_the_headers() {
+        let meta = get_meta(CLIENT_TRACEPARENT, Some("vendor=meta"));
+
+        let trace_context = get_request_trace_context(&meta, &get_extensions(HEADER_TRACEPARENT));
+
+        assert_eq!(
+            trace_context,
+            Some(TraceContext {
+                traceparent: CLIENT_TRACEPARENT.to_string(),
+                tracestate: Some("vendor=meta".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_traceparents_are_ignored() {
+        for traceparent in [
+            "not a traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(get_request_trace_context(&get_meta(traceparent, None), &Extensions::new()), None);
+        }
+
+        // A malformed one in `_meta` leaves the headers to go by
+        let trace_context = get_request_trace_context(&get_meta("garbage", None), &get_extensions(HEADER_TRACEPARENT));
+        assert_eq!(trace_context.unwrap().traceparent, HEADER_TRACEPARENT);
+    }
+
+    #[test]
+    fn invocations_continue_the_clients_trace_from_their_own_span() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let client = get_meta_trace_context(&get_meta(CLIENT_TRACEPARENT, Some("vendor=meta"))).unwrap();
+
+        let injected = tracing::subscriber::with_default(subscriber, || {
+            let span = get_invocation_span(&"counter(1)".to_string(), "increment");
+            let _ = span.set_parent(extract(&client));
+            inject(&span)
+        })
+        .unwrap();
+
+        assert_eq!(get_trace_id(&injected.traceparent), get_trace_id(CLIENT_TRACEPARENT));
+        assert_ne!(injected.traceparent, CLIENT_TRACEPARENT);
+        assert_eq!(injected.tracestate.as_deref(), Some("vendor=meta"));
+    }
+
+    #[test]
+    fn nothing_is_injected_without_the_opentelemetry_layer() {
+        let span = tracing::subscriber::with_default(tracing_subscriber::registry(), || {
+            get_invocation_span(&"counter(1)".to_string(), "increment")
+        });
+
+        assert_eq!(inject(&span), None);
+    }
+}
@@ -2,13 +2,49 @@ pub use agent_mcp_tool::*;
 pub use agent_mcp_server::*;
 pub use agent_mcp_capability::*;
 pub use agent_mcp_resource::*;
+pub use agent_mcp_management::*;
 pub use mcp_schema::*;
 pub use http_meta::*;
+pub use mcp_sessions::*;
+pub use mcp_server_context::*;
+pub use mcp_progress::*;
+pub use mcp_cancellation::*;
+pub use mcp_tasks::*;
+pub use mcp_elicitation::*;
+pub use mcp_sampling::*;
+pub use mcp_logging::*;
+pub use mcp_completion::*;
+pub use mcp_errors::*;
+pub use mcp_content::*;
+pub use mcp_tool_names::*;
+pub use mcp_metrics::*;
+pub use mcp_tracing::*;
+pub use mcp_audit::*;
+pub use mcp_rate_limits::*;
+pub use mcp_invocation_queue::*;
 
 mod agent_mcp_tool;
 mod agent_mcp_server;
 mod agent_mcp_capability;
 mod agent_mcp_resource;
+mod agent_mcp_management;
 mod mcp_schema;
 mod agent_mcp_prompt;
-mod http_meta;
\ No newline at end of file
+mod http_meta;
+mod mcp_sessions;
+mod mcp_server_context;
+mod mcp_progress;
+mod mcp_cancellation;
+mod mcp_tasks;
+mod mcp_elicitation;
+mod mcp_sampling;
+mod mcp_logging;
+mod mcp_completion;
+mod mcp_errors;
+mod mcp_content;
+mod mcp_tool_names;
+mod mcp_metrics;
+mod mcp_tracing;
+mod mcp_audit;
+mod mcp_rate_limits;
+mod mcp_invocation_queue;
\ No newline at end of file
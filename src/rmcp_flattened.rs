@@ -1,4 +1,5 @@
-// This is an example of MCP server where URL is not parameterized by agent-id
+// This is an example of MCP server where URL is not parameterized by agent-id,
+// instead every tool takes the constructor parameters of the agent to call as its `agent` argument
 
 use rmcp::transport::streamable_http_server::{
     session::local::LocalSessionManager,
@@ -15,22 +16,41 @@ use poem::{
 };
 use poem::endpoint::TowerCompatExt;
 
-use mcp_server::mcp_adaptor::GolemAgentMcpServer;
+use std::sync::Arc;
+use mcp_server::golem::{get_counter_agent_type, AgentEvents, AgentRegistry, DummyAgentInvoker};
+use mcp_server::mcp_adaptor::{check_tool_names, GolemAgentMcpServer, McpInstrumentedServer, McpLoggingLayer, McpServerContext};
 
 const BIND_ADDRESS: &str = "127.0.0.1:8000";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cancellation_token = tokio_util::sync::CancellationToken::new();
+    let shutdown_token = cancellation_token.clone();
+
+    // Redeploying an agent type through the registry rebuilds the tools of every live session.
+    // The sessions in the context are what `GolemAgentMcpServer::notify` pushes through,
+    // onto the standalone `GET /mcp` stream
+    let context = McpServerContext::new(
+        AgentRegistry::new([get_counter_agent_type()].into()),
+        Arc::new(DummyAgentInvoker),
+        Arc::new(AgentEvents::default()),
+    );
+
+    // Events during a session's requests also go to the client, once it sets a log level
     tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer())
+        .with(McpLoggingLayer::new(context.loggers.clone()))
         .init();
 
-    let cancellation_token = tokio_util::sync::CancellationToken::new();
-    let shutdown_token = cancellation_token.clone();
+    // Two methods ending up with the same tool name is a deployment mistake, better to refuse to start than to hide them
+    if let Err(errors) = check_tool_names(&context) {
+        let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+        anyhow::bail!("invalid tool names: {}", errors);
+    }
 
     // Base rmcp tower service
     let service = StreamableHttpService::new(
-        move || Ok(GolemAgentMcpServer::new(None)),
+        move || Ok(McpInstrumentedServer::new(GolemAgentMcpServer::new(None, context.clone()))),
         LocalSessionManager::default().into(),
         StreamableHttpServerConfig::default(),
     );
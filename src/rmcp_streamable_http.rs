@@ -9,6 +9,8 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
 use axum::routing::any;
 use tokio::sync::{RwLock};
 
@@ -21,7 +23,8 @@ use tracing_subscriber::{
     {self},
 };
 use mcp_server::golem::AgentId;
-use mcp_server::mcp_adaptor::GolemAgentMcpServer;
+use mcp_server::golem::{get_agent_type, get_counter_agent_type, AgentEvents, AgentRegistry, DummyAgentInvoker};
+use mcp_server::mcp_adaptor::{check_tool_names, GolemAgentMcpServer, McpInstrumentedServer, McpLoggingLayer, McpServerContext};
 
 const BIND_ADDRESS: &str = "127.0.0.1:8000";
 
@@ -36,21 +39,45 @@ Anyway, we can get over this limitation by making a few changes.
 */
 
 pub type ServiceMap = Arc<RwLock<HashMap<AgentId, StreamableHttpService<
-    GolemAgentMcpServer,
+    McpInstrumentedServer<GolemAgentMcpServer>,
     LocalSessionManager
 >>>>;
 
+#[derive(Clone)]
+pub struct AppState {
+    pub services: ServiceMap,
+    // Shared by all agents, so that a notification can target one session, an agent or everyone
+    pub context: McpServerContext,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let ct = tokio_util::sync::CancellationToken::new();
+
+    let state = AppState {
+        services: Arc::new(RwLock::new(HashMap::new())),
+        context: McpServerContext::new(
+            AgentRegistry::new([get_counter_agent_type()].into()),
+            Arc::new(DummyAgentInvoker),
+            Arc::new(AgentEvents::default()),
+        ),
+    };
+
+    // Events during a session's requests also go to the client, once it sets a log level
     tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer())
+        .with(McpLoggingLayer::new(state.context.loggers.clone()))
         .init();
-    let ct = tokio_util::sync::CancellationToken::new();
 
-    let services: ServiceMap = Arc::new(RwLock::new(HashMap::new()));
+    // Two methods ending up with the same tool name is a deployment mistake, better to refuse to start than to hide them
+    if let Err(errors) = check_tool_names(&state.context) {
+        let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ");
+        anyhow::bail!("invalid tool names: {}", errors);
+    }
 
+    // agent ids are `agent-type(params)`, e.g `/mcp/counter(1)`
     let router = axum::Router::new().route("/mcp/{agent_id}", any(mcp_entry).with_state(
-        services
+        state
     ));
 
     let tcp_listener = tokio::net::TcpListener::bind(BIND_ADDRESS).await?;
@@ -65,18 +92,22 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn mcp_entry(
-    State(services): State<ServiceMap>,
+    State(AppState { services, context }): State<AppState>,
     Path(agent_id): Path<String>,
     req: axum::http::Request<axum::body::Body>,
-) -> impl axum::response::IntoResponse {
+) -> axum::response::Response {
     if let Some(service) = services.read().await.get(&agent_id) {
-        return service.handle(req).await;
+        return service.handle(req).await.into_response();
+    }
+
+    if context.registry.get_constructor_schema(&get_agent_type(&agent_id)).is_none() {
+        return (StatusCode::NOT_FOUND, format!("unknown agent type: {}", get_agent_type(&agent_id))).into_response();
     }
 
     let service = StreamableHttpService::new(
         {
             let agent_id = agent_id.clone();
-            move || Ok(GolemAgentMcpServer::new(Some(agent_id.clone())))
+            move || Ok(McpInstrumentedServer::new(GolemAgentMcpServer::new(Some(agent_id.clone()), context.clone())))
         },
         LocalSessionManager::default().into(), // This I think needs to be distributed. otherwise handhshake will fail
         StreamableHttpServerConfig::default(),
@@ -84,5 +115,5 @@ async fn mcp_entry(
 
     services.write().await.insert(agent_id.clone(), service.clone());
 
-    service.handle(req).await
+    service.handle(req).await.into_response()
 }
@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::golem::{
+    AgentMethod, AgentMethodExposure, AgentMethodHints, AgentMethodTaskSupport, AgentType, AgentTypeDefinition, DataSchema,
+    DataSchemaEntry, ElementSchema,
+};
+use crate::server::{read_config_file, ServerConfigError};
+
+// The agent types to serve when the registry comes from a file rather than from golem, e.g
+//
+// agent_types:
+//   counter:
+//     constructor: [{ name: id, type: u32 }]
+//     methods:
+//       - name: increment
+//         input: [{ name: number, type: u32 }]
+//         output: [{ name: result, type: u32 }]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentManifest {
+    #[serde(default)]
+    pub agent_types: BTreeMap<AgentType, AgentTypeManifest>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentTypeManifest {
+    #[serde(default)]
+    pub constructor: Vec<ParameterManifest>,
+    #[serde(default)]
+    pub methods: Vec<MethodManifest>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MethodManifest {
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub input: Vec<ParameterManifest>,
+    #[serde(default)]
+    pub output: Vec<ParameterManifest>,
+    #[serde(default)]
+    pub hints: AgentMethodHints,
+    #[serde(default)]
+    pub task_support: AgentMethodTaskSupport,
+    #[serde(default)]
+    pub exposed_as: Vec<AgentMethodExposure>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParameterManifest {
+    pub name: String,
+    #[serde(flatten)]
+    pub schema: ElementManifest,
+    // Kept out of the audit log
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ElementManifest {
+    String,
+    U32,
+    Bool,
+    Enum {
+        values: Vec<String>,
+    },
+    Text {
+        #[serde(default)]
+        mime_type: Option<String>,
+    },
+    Binary {
+        #[serde(default)]
+        mime_type: Option<String>,
+    },
+}
+
+impl AgentManifest {
+    pub fn load(path: &Path) -> Result<Self, ServerConfigError> {
+        let value = read_config_file(path)?;
+
+        serde_json::from_value(value).map_err(|error| ServerConfigError::Parse {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        })
+    }
+
+    // Problems serde can't catch, as a list so that they are all reported at once
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+
+        for (agent_type, agent_type_manifest) in &self.agent_types {
+            // Agent ids are `agent-type(params)`, the type has to be told apart from the params
+            if agent_type.is_empty() {
+                errors.push("an agent type has an empty name".to_string());
+            } else if agent_type.contains('(') {
+                errors.push(format!("agent type `{}` has a `(` in its name", agent_type));
+            }
+
+            check_unique_names(
+                &format!("agent type `{}` constructor", agent_type),
+                agent_type_manifest.constructor.iter().map(|parameter| &parameter.name),
+                &mut errors,
+            );
+            check_unique_names(
+                &format!("agent type `{}`", agent_type),
+                agent_type_manifest.methods.iter().map(|method| &method.name),
+                &mut errors,
+            );
+
+            for method in &agent_type_manifest.methods {
+                check_unique_names(
+                    &format!("method `{}` of `{}` input", method.name, agent_type),
+                    method.input.iter().map(|parameter| &parameter.name),
+                    &mut errors,
+                );
+                check_unique_names(
+                    &format!("method `{}` of `{}` output", method.name, agent_type),
+                    method.output.iter().map(|parameter| &parameter.name),
+                    &mut errors,
+                );
+            }
+        }
+
+        errors
+    }
+
+    pub fn get_agent_types(&self) -> HashMap<AgentType, AgentTypeDefinition> {
+        self.agent_types
+            .iter()
+            .map(|(agent_type, agent_type_manifest)| {
+                let definition = AgentTypeDefinition {
+                    constructor_schema: get_data_schema(&agent_type_manifest.constructor),
+                    methods: agent_type_manifest.methods.iter().map(MethodManifest::get_agent_method).collect(),
+                };
+
+                (agent_type.clone(), definition)
+            })
+            .collect()
+    }
+}
+
+impl MethodManifest {
+    fn get_agent_method(&self) -> AgentMethod {
+        AgentMethod {
+            method_name: self.name.clone(),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            input_schema: get_data_schema(&self.input),
+            output_schema: get_data_schema(&self.output),
+            hints: self.hints.clone(),
+            task_support: self.task_support,
+            exposed_as: self.exposed_as.clone(),
+        }
+    }
+}
+
+fn get_data_schema(parameters: &[ParameterManifest]) -> DataSchema {
+    parameters
+        .iter()
+        .map(|parameter| {
+            let element_schema = match &parameter.schema {
+                ElementManifest::String => ElementSchema::String,
+                ElementManifest::U32 => ElementSchema::U32,
+                ElementManifest::Bool => ElementSchema::Bool,
+                ElementManifest::Enum { values } => ElementSchema::Enum(values.clone()),
+                ElementManifest::Text { mime_type } => ElementSchema::UnstructuredText { mime_type: mime_type.clone() },
+                ElementManifest::Binary { mime_type } => ElementSchema::UnstructuredBinary { mime_type: mime_type.clone() },
+            };
+
+            DataSchemaEntry::new(&parameter.name, element_schema).with_sensitive(parameter.sensitive)
+        })
+        .collect()
+}
+
+fn check_unique_names<'a>(context: &str, names: impl Iterator<Item = &'a String>, errors: &mut Vec<String>) {
+    let mut seen = vec![];
+
+    for name in names {
+        if name.is_empty() {
+            errors.push(format!("{} has an empty name", context));
+        } else if seen.contains(&name) {
+            errors.push(format!("{} has more than one `{}`", context, name));
+        } else {
+            seen.push(name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_manifest(agent_type: &str) -> AgentManifest {
+        AgentManifest {
+            agent_types: [(agent_type.to_string(), AgentTypeManifest::default())].into(),
+        }
+    }
+
+    #[test]
+    fn agent_type_names_have_to_be_told_apart_from_the_params() {
+        assert_eq!(get_manifest("counter").validate(), Vec::<String>::new());
+        assert_eq!(get_manifest("").validate(), vec!["an agent type has an empty name"]);
+        assert_eq!(get_manifest("counter(1)").validate(), vec!["agent type `counter(1)` has a `(` in its name"]);
+    }
+}
@@ -0,0 +1,294 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::golem::AgentRegistry;
+use crate::mcp_adaptor::{check_tool_names, warn_unreadable_resources, McpServerContext};
+use crate::server::{AgentManifest, ServerConfigError};
+
+// What the last reload did, for the health endpoint
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ManifestStatus {
+    pub path: String,
+    pub loaded_at: Option<DateTime<Utc>>,
+    // Set while the file on disk is broken, in which case the previous catalog is still served
+    pub last_error: Option<String>,
+    pub failed_at: Option<DateTime<Utc>>,
+}
+
+// Polls the manifest and replaces the registry's catalog when the file changes. Sessions rebuild their routers
+// and send list_changed off the registry events, like for any other deployment
+#[derive(Clone)]
+pub struct ManifestWatcher {
+    path: PathBuf,
+    context: McpServerContext,
+    status: Arc<RwLock<ManifestStatus>>,
+    // What was last applied (or rejected), so that an unchanged file is not parsed again
+    last_content: Arc<RwLock<Option<String>>>,
+}
+
+impl ManifestWatcher {
+    // The manifest is expected to be already loaded into the registry of the context. It is loaded once more here,
+    // so that what the watcher compares against is what the registry was built from, even if the file changed
+    // in between (which only sends events for what changed)
+    pub fn new(path: PathBuf, context: McpServerContext) -> Self {
+        let status = ManifestStatus {
+            path: path.display().to_string(),
+            loaded_at: Some(Utc::now()),
+            last_error: None,
+            failed_at: None,
+        };
+
+        let watcher = Self {
+            last_content: Arc::new(RwLock::new(None)),
+            path,
+            context,
+            status: Arc::new(RwLock::new(status)),
+        };
+        watcher.reload();
+
+        watcher
+    }
+
+    pub fn get_status(&self) -> ManifestStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    // Returns whether the file changed. A file that fails to parse or validate leaves the registry as it is
+    pub fn reload(&self) -> bool {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(error) => {
+                self.fail(format!("failed to read {}: {}", self.path.display(), error));
+                return false;
+            }
+        };
+
+        if self.last_content.read().unwrap().as_ref() == Some(&content) {
+            return false;
+        }
+
+        *self.last_content.write().unwrap() = Some(content);
+
+        match self.load() {
+            Ok(registry) => {
+                let changes = self.context.registry.replace_all(registry.get_agent_type_definitions().into_iter().collect());
+                warn_unreadable_resources(&self.context);
+
+                let mut status = self.status.write().unwrap();
+                status.loaded_at = Some(Utc::now());
+                status.last_error = None;
+                status.failed_at = None;
+                tracing::info!(path = %self.path.display(), ?changes, "agent manifest reloaded");
+            }
+            Err(error) => self.fail(error.to_string()),
+        }
+
+        true
+    }
+
+    // Checked as a whole, against a context that only differs by its registry, before anything is replaced
+    fn load(&self) -> Result<AgentRegistry, ServerConfigError> {
+        let manifest = AgentManifest::load(&self.path)?;
+
+        let errors = manifest.validate();
+        if !errors.is_empty() {
+            return Err(ServerConfigError::Invalid(errors));
+        }
+
+        let registry = AgentRegistry::new(manifest.get_agent_types());
+
+        let context = McpServerContext {
+            registry: registry.clone(),
+            ..self.context.clone()
+        };
+
+        check_tool_names(&context)
+            .map_err(|errors| ServerConfigError::Invalid(errors.iter().map(ToString::to_string).collect()))?;
+
+        Ok(registry)
+    }
+
+    fn fail(&self, error: String) {
+        let mut status = self.status.write().unwrap();
+
+        // A file that stays broken (or missing) is reported once
+        if status.last_error.as_ref() == Some(&error) {
+            return;
+        }
+
+        tracing::error!(path = %self.path.display(), %error, "agent manifest not reloaded, keeping the previous one");
+
+        status.last_error = Some(error);
+        status.failed_at = Some(Utc::now());
+    }
+
+    pub fn spawn(&self, interval: Duration, ct: CancellationToken) {
+        let watcher = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            loop {
+                tokio::select! {
+                    _ = ct.cancelled() => break,
+                    _ = ticks.tick() => {
+                        watcher.reload();
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use axum::extract::State;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use super::*;
+    use crate::golem::{AgentEvents, AgentRegistryEvent, DummyAgentInvoker};
+    use crate::server::{healthz, ServerHealth};
+
+    const MANIFEST: &str = r#"
+agent_types:
+  counter:
+    constructor: [{ name: id, type: u32 }]
+    methods:
+      - name: increment
+        input: [{ name: number, type: u32 }]
+  timer:
+    methods: [{ name: start }]
+  legacy:
+    methods: [{ name: ping }]
+"#;
+
+    const CHANGED_MANIFEST: &str = r#"
+agent_types:
+  counter:
+    constructor: [{ name: id, type: u32 }]
+    methods:
+      - name: increment
+        input: [{ name: number, type: u32 }]
+  timer:
+    methods: [{ name: start }, { name: stop }]
+  clock:
+    methods: [{ name: now }]
+"#;
+
+    // A manifest of its own for every test, removed when dropped
+    struct ManifestFile {
+        path: PathBuf,
+    }
+
+    impl ManifestFile {
+        fn new(content: &str) -> Self {
+            let file = Self {
+                path: std::env::temp_dir().join(format!("mcp-server-agents-{}.yaml", uuid::Uuid::new_v4())),
+            };
+            file.write(content);
+            file
+        }
+
+        fn write(&self, content: &str) {
+            std::fs::write(&self.path, content).unwrap();
+        }
+    }
+
+    impl Drop for ManifestFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn get_context(path: &Path) -> McpServerContext {
+        let registry = AgentRegistry::new(AgentManifest::load(path).unwrap().get_agent_types());
+        McpServerContext::new(registry, Arc::new(DummyAgentInvoker), Arc::new(AgentEvents::default()))
+    }
+
+    fn get_agent_types(context: &McpServerContext) -> Vec<String> {
+        let mut agent_types = context.registry.get_agent_types();
+        agent_types.sort();
+        agent_types
+    }
+
+    #[test]
+    fn a_changed_manifest_sends_events_for_what_changed_only() {
+        let file = ManifestFile::new(MANIFEST);
+        let context = get_context(&file.path);
+        let watcher = ManifestWatcher::new(file.path.clone(), context.clone());
+        let mut events = context.registry.subscribe();
+
+        assert!(!watcher.reload());
+
+        file.write(CHANGED_MANIFEST);
+        assert!(watcher.reload());
+
+        let mut received = vec![];
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        received.sort_by_key(|event| event.agent_type().clone());
+
+        // The counter is the same as it was
+        assert_eq!(
+            received,
+            vec![
+                AgentRegistryEvent::AgentTypeDeployed("clock".into()),
+                AgentRegistryEvent::AgentTypeRemoved("legacy".into()),
+                AgentRegistryEvent::AgentTypeDeployed("timer".into()),
+            ]
+        );
+        assert_eq!(get_agent_types(&context), vec!["clock", "counter", "timer"]);
+        assert!(!watcher.reload());
+    }
+
+    #[tokio::test]
+    async fn a_broken_manifest_keeps_the_previous_catalog() {
+        let file = ManifestFile::new(MANIFEST);
+        let context = get_context(&file.path);
+        let watcher = ManifestWatcher::new(file.path.clone(), context.clone());
+        let health = ServerHealth {
+            context: context.clone(),
+            manifest: Some(watcher.clone()),
+        };
+        let mut events = context.registry.subscribe();
+
+        file.write("agent_types:\n  \"counter(1)\":\n    methods: [{ name: increment }]\n");
+        assert!(watcher.reload());
+
+        assert_eq!(get_agent_types(&context), vec!["counter", "legacy", "timer"]);
+        assert_eq!(events.try_recv(), Err(TryRecvError::Empty));
+        assert!(watcher.get_status().last_error.unwrap().contains("has a `(` in its name"));
+
+        let status = healthz(State(health.clone())).await.0;
+        assert_eq!(status["status"], "degraded");
+
+        // Fixing it is picked up like any other change
+        file.write(CHANGED_MANIFEST);
+        assert!(watcher.reload());
+
+        assert_eq!(get_agent_types(&context), vec!["clock", "counter", "timer"]);
+        assert_eq!(watcher.get_status().last_error, None);
+
+        let status = healthz(State(health)).await.0;
+        assert_eq!(status["status"], "ok");
+    }
+
+    #[test]
+    fn a_manifest_changed_before_the_watcher_started_is_not_missed() {
+        let file = ManifestFile::new(MANIFEST);
+        let context = get_context(&file.path);
+
+        file.write(CHANGED_MANIFEST);
+        let watcher = ManifestWatcher::new(file.path.clone(), context.clone());
+
+        assert_eq!(get_agent_types(&context), vec!["clock", "counter", "timer"]);
+        assert!(!watcher.reload());
+    }
+}
@@ -0,0 +1,16 @@
+// What turns the adaptor into a deployable server: config file, agent manifest, auth, metrics and the http routes
+pub use agent_manifest::*;
+pub use manifest_watcher::*;
+pub use server_app::*;
+pub use server_auth::*;
+pub use server_config::*;
+pub use server_health::*;
+pub use server_metrics::*;
+
+mod agent_manifest;
+mod manifest_watcher;
+mod server_app;
+mod server_auth;
+mod server_config;
+mod server_health;
+mod server_metrics;
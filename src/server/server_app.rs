@@ -0,0 +1,384 @@
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{any, get};
+use http_body_util::Limited;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::golem::{get_agent_type, AgentEvents, AgentId, DummyAgentInvoker};
+use crate::mcp_adaptor::{
+    check_tool_names, warn_unreadable_resources, AuthPrincipal, GolemAgentMcpServer,
+    JsonLinesAuditSink, McpInstrumentedServer, McpLoggers, McpLoggingLayer, McpMeteredServer,
+    McpMetrics, McpServerContext, RateLimit, RateLimitKey, RateLimitScope, RateLimiter,
+    DEFAULT_TASK_TTL,
+};
+use crate::server::{
+    authenticate, healthz, info, metrics, readyz, track_sse_streams, BearerTokens, LogFormat,
+    ManifestWatcher, RegistryConfig, RouteLayout, ServerConfig, ServerConfigError, ServerHealth,
+    SessionBackend,
+};
+
+type McpService = StreamableHttpService<
+    McpInstrumentedServer<McpMeteredServer<GolemAgentMcpServer>>,
+    LocalSessionManager,
+>;
+
+// The context every session shares, with tool names checked so that a clash fails the startup
+pub fn build_context(config: &ServerConfig) -> Result<McpServerContext, ServerConfigError> {
+    let context = build_checked_context(config)?;
+
+    match &config.audit.path {
+        Some(path) => {
+            let audit =
+                JsonLinesAuditSink::open(path).map_err(|error| ServerConfigError::Read {
+                    path: path.display().to_string(),
+                    message: format!("failed to open the audit log: {}", error),
+                })?;
+            Ok(context.with_audit(Arc::new(audit)))
+        }
+        None => Ok(context),
+    }
+}
+
+// What `build_context` checks, without opening (or creating) the audit log, for `--check-config`
+pub fn check_context(config: &ServerConfig) -> Result<(), ServerConfigError> {
+    build_checked_context(config).map(|_| ())
+}
+
+fn build_checked_context(config: &ServerConfig) -> Result<McpServerContext, ServerConfigError> {
+    let context = McpServerContext::new(
+        config.registry.load()?,
+        Arc::new(DummyAgentInvoker),
+        Arc::new(AgentEvents::default()),
+    )
+    .with_mapping_rules(config.mcp.mapping_rules.clone())
+    .with_management_tools(config.mcp.management_tools)
+    .with_rate_limits(config.mcp.rate_limits.clone())
+    .with_invocation_limits(config.mcp.invocations.clone())
+    .with_task_ttl(
+        config
+            .mcp
+            .task_ttl_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TASK_TTL),
+    );
+
+    check_tool_names(&context).map_err(|errors| {
+        ServerConfigError::Invalid(errors.iter().map(ToString::to_string).collect())
+    })?;
+
+    Ok(context)
+}
+
+// Events during a session's requests also go to the client, once it sets a log level. The returned provider
+// is to be shut down on exit, for the last spans to make it to the collector
+pub fn init_logging(
+    config: &ServerConfig,
+    loggers: McpLoggers,
+) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let fmt_layer = match config.logging.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+    };
+
+    // Without an exporter spans still get ids, so that agent invocations carry a trace context of their own
+    let mut provider = SdkTracerProvider::builder().with_resource(
+        Resource::builder()
+            .with_service_name(config.tracing.service_name.clone())
+            .build(),
+    );
+
+    if let Some(otlp_endpoint) = &config.tracing.otlp_endpoint {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(otlp_endpoint)
+            .build()?;
+        provider = provider.with_batch_exporter(exporter);
+    }
+
+    let provider = provider.build();
+    let otel_layer =
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")));
+
+    // The filter only applies to the local output and the exported spans, the sessions pick their own level
+    tracing_subscriber::registry()
+        .with(fmt_layer.with_filter(EnvFilter::new(&config.logging.filter)))
+        .with(otel_layer.with_filter(EnvFilter::new(&config.logging.filter)))
+        .with(McpLoggingLayer::new(loggers))
+        .init();
+
+    Ok(provider)
+}
+
+pub fn build_router(
+    config: &ServerConfig,
+    context: McpServerContext,
+    health: ServerHealth,
+    ct: CancellationToken,
+) -> axum::Router {
+    let http_config = StreamableHttpServerConfig {
+        sse_keep_alive: config.sessions.keep_alive_secs.map(Duration::from_secs),
+        stateful_mode: config.sessions.stateful,
+        cancellation_token: ct,
+        ..Default::default()
+    };
+
+    let metrics_state = context.metrics.clone();
+
+    let router = match config.routes.layout {
+        RouteLayout::Global => {
+            let service = new_service(None, context, http_config, config.sessions.backend);
+            axum::Router::new().route_service(&config.routes.path, service)
+        }
+        // agent ids are `agent-type(params)`, e.g `/mcp/counter(1)`
+        RouteLayout::PerAgent => {
+            let state = PerAgentState {
+                services: Arc::new(RwLock::new(HashMap::new())),
+                context,
+                http_config,
+                session_backend: config.sessions.backend,
+            };
+
+            axum::Router::new().route(
+                &format!("{}/{{agent_id}}", config.routes.path),
+                any(per_agent_entry).with_state(state),
+            )
+        }
+    };
+
+    let router = router.layer(middleware::from_fn_with_state(
+        config.limits.max_request_body_bytes,
+        limit_body,
+    ));
+
+    // Inside of authentication, as it goes by principal
+    let router = match config.limits.http_rate_limit {
+        Some(limit) => {
+            let state = HttpRateLimit {
+                limit,
+                limiter: RateLimiter::default(),
+                metrics: metrics_state.clone(),
+            };
+            router.layer(middleware::from_fn_with_state(state, rate_limit_requests))
+        }
+        None => router,
+    };
+
+    let status = axum::Router::new()
+        .route("/info", get(info))
+        .route("/metrics", get(metrics))
+        .with_state(health.clone());
+
+    let (router, status) = match config.auth.public_info_and_metrics {
+        true => (router, status),
+        false => (router.merge(status), axum::Router::new()),
+    };
+
+    router
+        .layer(middleware::from_fn_with_state(
+            BearerTokens::new(&config.auth),
+            authenticate,
+        ))
+        .layer(middleware::from_fn_with_state(
+            metrics_state,
+            track_sse_streams,
+        ))
+        .merge(status)
+        .merge(
+            axum::Router::new()
+                .route("/healthz", get(healthz))
+                .route("/readyz", get(readyz))
+                .with_state(health),
+        )
+}
+
+pub async fn run(config: ServerConfig, context: McpServerContext) -> std::io::Result<()> {
+    let ct = CancellationToken::new();
+    warn_unreadable_resources(&context);
+
+    let manifest = match &config.registry {
+        RegistryConfig::Manifest {
+            path,
+            reload: true,
+            reload_interval_secs,
+        } => {
+            let watcher = ManifestWatcher::new(path.clone(), context.clone());
+            watcher.spawn(Duration::from_secs(*reload_interval_secs), ct.clone());
+            Some(watcher)
+        }
+        _ => None,
+    };
+
+    let health = ServerHealth {
+        context: context.clone(),
+        manifest,
+    };
+
+    let router = build_router(&config, context, health, ct.clone());
+
+    let tcp_listener = tokio::net::TcpListener::bind(&config.bind).await?;
+    tracing::info!(bind = %config.bind, path = %config.routes.path, layout = ?config.routes.layout, "mcp server listening");
+
+    // The client address is what http requests are rate limited by without auth
+    axum::serve(
+        tcp_listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        let _ = tokio::signal::ctrl_c().await;
+        ct.cancel();
+    })
+    .await
+}
+
+fn new_service(
+    agent_id: Option<AgentId>,
+    context: McpServerContext,
+    http_config: StreamableHttpServerConfig,
+    session_backend: SessionBackend,
+) -> McpService {
+    let session_manager = match session_backend {
+        SessionBackend::Memory => LocalSessionManager::default(),
+    };
+
+    StreamableHttpService::new(
+        move || {
+            let server = GolemAgentMcpServer::new(agent_id.clone(), context.clone());
+            Ok(McpInstrumentedServer::new(McpMeteredServer::new(
+                server,
+                context.metrics.clone(),
+            )))
+        },
+        session_manager.into(),
+        http_config,
+    )
+}
+
+#[derive(Clone)]
+struct PerAgentState {
+    services: Arc<RwLock<HashMap<AgentId, McpService>>>,
+    context: McpServerContext,
+    http_config: StreamableHttpServerConfig,
+    session_backend: SessionBackend,
+}
+
+async fn per_agent_entry(
+    State(state): State<PerAgentState>,
+    Path(agent_id): Path<AgentId>,
+    request: Request,
+) -> Response {
+    if let Some(service) = state.services.read().await.get(&agent_id) {
+        return service.handle(request).await.into_response();
+    }
+
+    // Anything in the path would otherwise get a service of its own, and an agent type label in the metrics
+    if state
+        .context
+        .registry
+        .get_constructor_schema(&get_agent_type(&agent_id))
+        .is_none()
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("unknown agent type: {}", get_agent_type(&agent_id)),
+        )
+            .into_response();
+    }
+
+    let service = state
+        .services
+        .write()
+        .await
+        .entry(agent_id.clone())
+        .or_insert_with(|| {
+            new_service(
+                Some(agent_id),
+                state.context.clone(),
+                state.http_config.clone(),
+                state.session_backend,
+            )
+        })
+        .clone();
+
+    service.handle(request).await.into_response()
+}
+
+// Content-Length is checked upfront, and the body is cut off at the limit for anything streamed
+async fn limit_body(State(max_bytes): State<usize>, request: Request, next: Next) -> Response {
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+
+    if content_length.is_some_and(|content_length| content_length > max_bytes) {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    next.run(Request::from_parts(
+        parts,
+        Body::new(Limited::new(body, max_bytes)),
+    ))
+    .await
+}
+
+#[derive(Clone)]
+struct HttpRateLimit {
+    limit: RateLimit,
+    limiter: RateLimiter,
+    metrics: McpMetrics,
+}
+
+async fn rate_limit_requests(
+    State(rate_limit): State<HttpRateLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = match request.extensions().get::<AuthPrincipal>() {
+        Some(principal) => format!("principal:{}", principal.0),
+        None => match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(address)) => format!("address:{}", address.ip()),
+            None => "anonymous".to_string(),
+        },
+    };
+
+    let keys = [(
+        RateLimitKey {
+            scope: RateLimitScope::Http,
+            key,
+        },
+        rate_limit.limit,
+    )];
+
+    match rate_limit.limiter.try_acquire(&keys) {
+        Ok(()) => next.run(request).await,
+        Err(exceeded) => {
+            rate_limit.metrics.observe_rate_limited(exceeded.scope);
+
+            // Whole seconds, rounded up so that a client waiting for it doesn't come back too early
+            let retry_after = exceeded.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+            )
+                .into_response()
+        }
+    }
+}
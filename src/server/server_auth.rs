@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::mcp_adaptor::AuthPrincipal;
+use crate::server::AuthConfig;
+
+// Token to principal
+#[derive(Clone, Default)]
+pub struct BearerTokens {
+    tokens: Arc<HashMap<String, String>>,
+}
+
+impl BearerTokens {
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            tokens: Arc::new(
+                config
+                    .bearer_tokens
+                    .iter()
+                    .map(|bearer_token| (bearer_token.token.clone(), bearer_token.principal.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    pub fn get_principal(&self, token: &str) -> Option<AuthPrincipal> {
+        self.tokens.get(token).cloned().map(AuthPrincipal)
+    }
+}
+
+pub async fn authenticate(State(tokens): State<BearerTokens>, mut request: Request, next: Next) -> Response {
+    if !tokens.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let principal = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| tokens.get_principal(token.trim()));
+
+    match principal {
+        Some(principal) => {
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        None => (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response(),
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing_subscriber::EnvFilter;
+use url::Url;
+
+use crate::golem::{get_counter_agent_type, AgentRegistry};
+use crate::mcp_adaptor::{McpInvocationLimits, McpMappingRules, McpRateLimits, RateLimit};
+use crate::server::AgentManifest;
+
+// Any setting can be overridden from the environment, with `__` between the keys of its path,
+// e.g `MCP_SERVER__BIND=0.0.0.0:8000` or `MCP_SERVER__ROUTES__LAYOUT=per_agent`.
+// Settings that are strings take the value as it is, anything else is parsed as json if it can be.
+// Quoted or bracketed values are always json, e.g `MCP_SERVER__TRACING__OTLP_ENDPOINT='"123"'`
+pub const CONFIG_ENV_PREFIX: &str = "MCP_SERVER__";
+
+// Everything the `mcp-server` binary needs, from a toml, yaml or json file. Every section is optional
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub routes: RoutesConfig,
+    pub registry: RegistryConfig,
+    pub mcp: McpConfig,
+    pub auth: AuthConfig,
+    pub sessions: SessionsConfig,
+    pub limits: LimitsConfig,
+    pub logging: LoggingConfig,
+    pub tracing: TracingConfig,
+    pub audit: AuditConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1:8000".to_string(),
+            routes: RoutesConfig::default(),
+            registry: RegistryConfig::default(),
+            mcp: McpConfig::default(),
+            auth: AuthConfig::default(),
+            sessions: SessionsConfig::default(),
+            limits: LimitsConfig::default(),
+            logging: LoggingConfig::default(),
+            tracing: TracingConfig::default(),
+            audit: AuditConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteLayout {
+    // A single endpoint for every agent, where tools take the agent as an argument
+    #[default]
+    Global,
+    // An endpoint per agent, `{path}/{agent_id}`
+    PerAgent,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutesConfig {
+    pub layout: RouteLayout,
+    pub path: String,
+}
+
+impl Default for RoutesConfig {
+    fn default() -> Self {
+        Self {
+            layout: RouteLayout::Global,
+            path: "/mcp".to_string(),
+        }
+    }
+}
+
+// Where the agent types come from. A relative manifest path is relative to the config file
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RegistryConfig {
+    // Just the counter, as in the examples
+    #[default]
+    Builtin,
+    Manifest {
+        path: PathBuf,
+        // Whether changes to the file are picked up without a restart, and how often it is checked
+        #[serde(default = "default_reload")]
+        reload: bool,
+        #[serde(default = "default_reload_interval_secs")]
+        reload_interval_secs: u64,
+    },
+}
+
+fn default_reload() -> bool {
+    true
+}
+
+fn default_reload_interval_secs() -> u64 {
+    5
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpConfig {
+    pub mapping_rules: McpMappingRules,
+    pub management_tools: bool,
+    // Tool calls over a limit get a `rate_limited` tool error with the time to wait
+    pub rate_limits: McpRateLimits,
+    // How agent invocations queue up, a full queue gets an `overloaded` tool error
+    pub invocations: McpInvocationLimits,
+    // How long tasks and their results are kept, an hour if left out
+    pub task_ttl_secs: Option<u64>,
+}
+
+// No tokens means no authentication at all
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub bearer_tokens: Vec<BearerTokenConfig>,
+    // `/info` and `/metrics` take a token like the mcp routes unless this is set, `/healthz` and `/readyz` never do
+    pub public_info_and_metrics: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BearerTokenConfig {
+    // Who the token belongs to, as seen by the handlers (see `AuthPrincipal`)
+    pub principal: String,
+    pub token: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionBackend {
+    // Sessions live in the process, so clients have to stick to one instance
+    #[default]
+    Memory,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionsConfig {
+    pub backend: SessionBackend,
+    pub stateful: bool,
+    // Pings on open sse streams, so that proxies don't close them
+    pub keep_alive_secs: Option<u64>,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            backend: SessionBackend::Memory,
+            stateful: true,
+            keep_alive_secs: Some(15),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_request_body_bytes: usize,
+    // Requests to the mcp routes, by principal (or client address without auth). Over it is a 429 with `Retry-After`
+    pub http_rate_limit: Option<RateLimit>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_request_body_bytes: 4 * 1024 * 1024,
+            http_rate_limit: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // An `EnvFilter` directive, e.g `info` or `info,mcp_server=debug`
+    pub filter: String,
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+// Requests always continue the trace the client sent (`traceparent` in the http headers or in `_meta`)
+// and pass it on to the agent invocations, spans are only exported with an endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TracingConfig {
+    // An OTLP/HTTP collector, e.g `http://localhost:4318/v1/traces`
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "mcp-server".to_string(),
+        }
+    }
+}
+
+// Agent method calls are appended to `path` as json lines, with the parameters marked `sensitive`
+// in the agent manifest redacted
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    // Relative to the config file, no path (the default) means no audit log
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerConfigError {
+    Read { path: String, message: String },
+    Parse { path: String, message: String },
+    Invalid(Vec<String>),
+}
+
+impl Display for ServerConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerConfigError::Read { path, message } => write!(f, "failed to read {}: {}", path, message),
+            ServerConfigError::Parse { path, message } => write!(f, "failed to parse {}: {}", path, message),
+            ServerConfigError::Invalid(errors) => write!(f, "invalid config:\n  {}", errors.join("\n  ")),
+        }
+    }
+}
+
+impl std::error::Error for ServerConfigError {}
+
+impl ServerConfig {
+    // Without a file, this is the defaults along with the environment overrides
+    pub fn load(path: Option<&Path>) -> Result<Self, ServerConfigError> {
+        Self::load_with_env(path, std::env::vars())
+    }
+
+    fn load_with_env(path: Option<&Path>, vars: impl Iterator<Item = (String, String)>) -> Result<Self, ServerConfigError> {
+        let mut value = match path {
+            Some(path) => read_config_file(path)?,
+            None => Value::Object(Map::new()),
+        };
+
+        apply_env_overrides(&mut value, vars);
+
+        let mut config: ServerConfig = serde_json::from_value(value).map_err(|error| ServerConfigError::Parse {
+            path: path.map(|path| path.display().to_string()).unwrap_or("environment".to_string()),
+            message: error.to_string(),
+        })?;
+
+        if let (RegistryConfig::Manifest { path: manifest_path, .. }, Some(dir)) =
+            (&mut config.registry, path.and_then(Path::parent))
+        {
+            if manifest_path.is_relative() {
+                *manifest_path = dir.join(&*manifest_path);
+            }
+        }
+
+        if let (Some(audit_path), Some(dir)) = (&mut config.audit.path, path.and_then(Path::parent)) {
+            if audit_path.is_relative() {
+                *audit_path = dir.join(&*audit_path);
+            }
+        }
+
+        Ok(config)
+    }
+
+    // Everything that can be checked without binding anything, all at once
+    pub fn validate(&self) -> Result<(), ServerConfigError> {
+        let mut errors = vec![];
+
+        if let Err(error) = self.bind.parse::<SocketAddr>() {
+            errors.push(format!("bind `{}` is not a socket address: {}", self.bind, error));
+        }
+
+        if !self.routes.path.starts_with('/') || self.routes.path.ends_with('/') {
+            errors.push(format!("routes.path `{}` must start with `/` and not end with one", self.routes.path));
+        }
+
+        if let RegistryConfig::Manifest { path, reload_interval_secs, .. } = &self.registry {
+            match AgentManifest::load(path) {
+                Ok(manifest) => errors.extend(manifest.validate()),
+                Err(error) => errors.push(error.to_string()),
+            }
+
+            if *reload_interval_secs == 0 {
+                errors.push("registry.reload_interval_secs must be positive, set `reload = false` to turn reloading off".to_string());
+            }
+        }
+
+        let mut tokens = HashSet::new();
+
+        for bearer_token in &self.auth.bearer_tokens {
+            if bearer_token.principal.is_empty() || bearer_token.token.is_empty() {
+                errors.push("auth.bearer_tokens need a principal and a token".to_string());
+            } else if !tokens.insert(&bearer_token.token) {
+                errors.push(format!("auth.bearer_tokens has the token of `{}` more than once", bearer_token.principal));
+            }
+        }
+
+        if self.sessions.keep_alive_secs == Some(0) {
+            errors.push("sessions.keep_alive_secs must be positive, leave it out to turn keep-alive off".to_string());
+        }
+
+        if self.limits.max_request_body_bytes == 0 {
+            errors.push("limits.max_request_body_bytes must be positive".to_string());
+        }
+
+        let rate_limits = [
+            ("limits.http_rate_limit", self.limits.http_rate_limit),
+            ("mcp.rate_limits.per_principal", self.mcp.rate_limits.per_principal),
+            ("mcp.rate_limits.per_session", self.mcp.rate_limits.per_session),
+            ("mcp.rate_limits.per_agent", self.mcp.rate_limits.per_agent),
+            ("mcp.rate_limits.per_tool", self.mcp.rate_limits.per_tool),
+        ];
+
+        for (name, rate_limit) in rate_limits {
+            if let Some(Err(error)) = rate_limit.map(|rate_limit| rate_limit.validate()) {
+                errors.push(format!("{} {}", name, error));
+            }
+        }
+
+        if self.mcp.task_ttl_secs == Some(0) {
+            errors.push("mcp.task_ttl_secs must be positive".to_string());
+        }
+
+        if let Err(error) = self.mcp.invocations.validate() {
+            errors.push(format!("mcp.invocations {}", error));
+        }
+
+        if let Err(error) = EnvFilter::try_new(&self.logging.filter) {
+            errors.push(format!("logging.filter `{}` is invalid: {}", self.logging.filter, error));
+        }
+
+        // The file itself is only created once the server starts
+        if let Some(audit_path) = &self.audit.path {
+            if audit_path.is_dir() || audit_path.parent().is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir()) {
+                errors.push(format!("audit.path `{}` must be a file in an existing directory", audit_path.display()));
+            }
+        }
+
+        if let Some(otlp_endpoint) = &self.tracing.otlp_endpoint {
+            match Url::parse(otlp_endpoint) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => errors.push(format!("tracing.otlp_endpoint `{}` must be an http(s) url", otlp_endpoint)),
+                Err(error) => errors.push(format!("tracing.otlp_endpoint `{}` is invalid: {}", otlp_endpoint, error)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ServerConfigError::Invalid(errors))
+        }
+    }
+}
+
+impl RegistryConfig {
+    pub fn load(&self) -> Result<AgentRegistry, ServerConfigError> {
+        match self {
+            RegistryConfig::Builtin => Ok(AgentRegistry::new([get_counter_agent_type()].into())),
+            RegistryConfig::Manifest { path, .. } => {
+                let manifest = AgentManifest::load(path)?;
+                Ok(AgentRegistry::new(manifest.get_agent_types()))
+            }
+        }
+    }
+}
+
+// toml, yaml or json going by the extension, as json so that the overrides don't care where it came from
+pub fn read_config_file(path: &Path) -> Result<Value, ServerConfigError> {
+    let display_path = path.display().to_string();
+
+    let content = std::fs::read_to_string(path).map_err(|error| ServerConfigError::Read {
+        path: display_path.clone(),
+        message: error.to_string(),
+    })?;
+
+    let parse_error = |message: String| ServerConfigError::Parse {
+        path: display_path.clone(),
+        message,
+    };
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|error| parse_error(error.to_string())),
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content).map_err(|error| parse_error(error.to_string())),
+        Some("json") => serde_json::from_str(&content).map_err(|error| parse_error(error.to_string())),
+        _ => Err(parse_error("expected a .toml, .yaml, .yml or .json file".to_string())),
+    }
+}
+
+pub fn apply_env_overrides(value: &mut Value, vars: impl Iterator<Item = (String, String)>) {
+    let defaults = serde_json::to_value(ServerConfig::default()).unwrap_or_default();
+
+    for (name, env_value) in vars {
+        let Some(path) = name.strip_prefix(CONFIG_ENV_PREFIX) else {
+            continue;
+        };
+
+        let keys = path.split("__").map(str::to_lowercase).collect::<Vec<_>>();
+
+        // What is there already, from the file or the defaults, says whether `123` is meant as a string
+        let is_string = matches!(get_path(value, &keys).or_else(|| get_path(&defaults, &keys)), Some(Value::String(_)));
+        let is_json = !is_string || env_value.starts_with(['"', '[', '{']);
+
+        let env_value = if is_json {
+            serde_json::from_str(&env_value).unwrap_or(Value::String(env_value))
+        } else {
+            Value::String(env_value)
+        };
+
+        set_path(value, &keys, env_value);
+    }
+}
+
+fn get_path<'a>(value: &'a Value, keys: &[String]) -> Option<&'a Value> {
+    keys.iter().try_fold(value, |value, key| value.get(key))
+}
+
+fn set_path(value: &mut Value, keys: &[String], new_value: Value) {
+    let Some((key, rest)) = keys.split_first() else {
+        *value = new_value;
+        return;
+    };
+
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+
+    if let Value::Object(fields) = value {
+        set_path(fields.entry(key.clone()).or_insert(Value::Null), rest, new_value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A config file of its own for every test, removed when dropped
+    struct ConfigFile {
+        path: PathBuf,
+    }
+
+    impl ConfigFile {
+        fn new(content: &str) -> Self {
+            Self::with_extension(content, "toml")
+        }
+
+        fn with_extension(content: &str, extension: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("mcp-server-{}.{}", uuid::Uuid::new_v4(), extension));
+            std::fs::write(&path, content).unwrap();
+            Self { path }
+        }
+    }
+
+    impl Drop for ConfigFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn load(path: Option<&Path>, vars: &[(&str, &str)]) -> ServerConfig {
+        let vars = vars.iter().map(|(name, value)| (name.to_string(), value.to_string()));
+        ServerConfig::load_with_env(path, vars).unwrap()
+    }
+
+    #[test]
+    fn env_overrides_the_file_and_the_file_the_defaults() {
+        let file = ConfigFile::new(
+            r#"
+            bind = "0.0.0.0:9000"
+
+            [routes]
+            layout = "per_agent"
+
+            [sessions]
+            keep_alive_secs = 5
+            "#,
+        );
+
+        let config = load(
+            Some(&file.path),
+            &[
+                ("MCP_SERVER__SESSIONS__KEEP_ALIVE_SECS", "30"),
+                ("MCP_SERVER__LOGGING__FORMAT", "json"),
+                ("MCP_SERVER__MCP__MANAGEMENT_TOOLS", "true"),
+                ("OTHER__BIND", "ignored"),
+            ],
+        );
+
+        assert_eq!(config.bind, "0.0.0.0:9000");
+        assert_eq!(config.routes.layout, RouteLayout::PerAgent);
+        assert_eq!(config.routes.path, "/mcp");
+        assert_eq!(config.sessions.keep_alive_secs, Some(30));
+        assert!(config.sessions.stateful);
+        assert_eq!(config.logging.format, LogFormat::Json);
+        assert!(config.mcp.management_tools);
+    }
+
+    #[test]
+    fn string_settings_take_the_value_as_it_is() {
+        let file = ConfigFile::new("[routes]\npath = \"/mcp\"\n");
+
+        let config = load(
+            Some(&file.path),
+            &[
+                ("MCP_SERVER__ROUTES__PATH", "123"),
+                ("MCP_SERVER__TRACING__SERVICE_NAME", "true"),
+                ("MCP_SERVER__LOGGING__FILTER", "[info]"),
+            ],
+        );
+
+        assert_eq!(config.routes.path, "123");
+        assert_eq!(config.tracing.service_name, "true");
+        // Bracketed, but not json after all
+        assert_eq!(config.logging.filter, "[info]");
+    }
+
+    #[test]
+    fn quoted_or_bracketed_values_are_json() {
+        let config = load(
+            None,
+            &[
+                ("MCP_SERVER__TRACING__OTLP_ENDPOINT", r#""123""#),
+                ("MCP_SERVER__AUTH__BEARER_TOKENS", r#"[{"principal": "ann", "token": "123"}]"#),
+            ],
+        );
+
+        assert_eq!(config.tracing.otlp_endpoint.as_deref(), Some("123"));
+        assert_eq!(
+            config.auth.bearer_tokens,
+            vec![BearerTokenConfig {
+                principal: "ann".to_string(),
+                token: "123".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn env_alone_builds_on_the_defaults() {
+        let config = load(
+            None,
+            &[
+                ("MCP_SERVER__REGISTRY__SOURCE", "manifest"),
+                ("MCP_SERVER__REGISTRY__PATH", "agents.yaml"),
+                ("MCP_SERVER__REGISTRY__RELOAD_INTERVAL_SECS", "10"),
+            ],
+        );
+
+        assert_eq!(
+            config.registry,
+            RegistryConfig::Manifest {
+                path: PathBuf::from("agents.yaml"),
+                reload: true,
+                reload_interval_secs: 10,
+            }
+        );
+        assert_eq!(config.bind, ServerConfig::default().bind);
+    }
+
+    #[test]
+    fn relative_paths_are_relative_to_the_file() {
+        let file = ConfigFile::new(
+            r#"
+            [registry]
+            source = "manifest"
+            path = "agents.yaml"
+
+            [audit]
+            path = "audit.jsonl"
+            "#,
+        );
+        let dir = file.path.parent().unwrap();
+
+        let config = load(Some(&file.path), &[]);
+
+        assert!(matches!(&config.registry, RegistryConfig::Manifest { path, .. } if path == &dir.join("agents.yaml")));
+        assert_eq!(config.audit.path, Some(dir.join("audit.jsonl")));
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        let vars = [("MCP_SERVER__SESIONS__STATEFUL".to_string(), "false".to_string())];
+        assert!(matches!(
+            ServerConfig::load_with_env(None, vars.into_iter()),
+            Err(ServerConfigError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn json_files_are_read_and_other_extensions_named() {
+        let file = ConfigFile::with_extension(r#"{"bind": "0.0.0.0:9000"}"#, "json");
+        assert_eq!(load(Some(&file.path), &[]).bind, "0.0.0.0:9000");
+
+        let file = ConfigFile::with_extension("bind = 1", "ini");
+        match ServerConfig::load_with_env(Some(&file.path), std::iter::empty()) {
+            Err(ServerConfigError::Parse { message, .. }) => assert_eq!(message, "expected a .toml, .yaml, .yml or .json file"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::golem::AgentId;
+use crate::mcp_adaptor::{McpServerContext, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::server::ManifestWatcher;
+
+// Operational endpoints, next to the mcp routes. Only `/info` and `/metrics` can be behind auth, see `AuthConfig`
+#[derive(Clone)]
+pub struct ServerHealth {
+    pub context: McpServerContext,
+    pub manifest: Option<ManifestWatcher>,
+}
+
+// Liveness. A manifest that failed to reload makes the server degraded, though it keeps serving the previous catalog
+pub async fn healthz(State(health): State<ServerHealth>) -> Json<Value> {
+    let manifest = health.manifest.as_ref().map(ManifestWatcher::get_status);
+
+    let status = match &manifest {
+        Some(manifest) if manifest.last_error.is_some() => "degraded",
+        _ => "ok",
+    };
+
+    Json(json!({
+        "status": status,
+        "manifest": manifest,
+    }))
+}
+
+// Ready once there is something to serve and the backend answers
+pub async fn readyz(State(health): State<ServerHealth>) -> (StatusCode, Json<Value>) {
+    let agent_types = health.context.registry.get_agent_types().len();
+
+    let registry = if agent_types > 0 {
+        json!({"ok": true, "agent_types": agent_types})
+    } else {
+        json!({"ok": false, "error": "no agent types loaded"})
+    };
+
+    let invoker = match health.context.invoker.check_health().await {
+        Ok(()) => json!({"ok": true}),
+        Err(error) => json!({"ok": false, "error": error.to_string()}),
+    };
+
+    let ready = registry["ok"] == true && invoker["ok"] == true;
+
+    let status_code = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (
+        status_code,
+        Json(json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "checks": {
+                "registry": registry,
+                "invoker": invoker,
+            },
+        })),
+    )
+}
+
+pub async fn info(State(health): State<ServerHealth>) -> Json<Value> {
+    let sessions = health.context.sessions.count_by_agent().await;
+
+    let global_sessions = sessions.get(&None).copied().unwrap_or_default();
+
+    // Agent ids can carry sensitive constructor params, ids that only differ in those are counted together
+    let mut agent_sessions = BTreeMap::<AgentId, usize>::new();
+    for (agent_id, count) in &sessions {
+        if let Some(agent_id) = agent_id {
+            *agent_sessions.entry(health.context.registry.redact_agent_id(agent_id)).or_default() += count;
+        }
+    }
+
+    Json(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol_versions": SUPPORTED_PROTOCOL_VERSIONS.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "agent_types": health.context.registry.get_agent_types(),
+        "sessions": {
+            "total": sessions.values().sum::<usize>(),
+            "global": global_sessions,
+            "per_agent": agent_sessions,
+        },
+    }))
+}
@@ -0,0 +1,43 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures::StreamExt;
+use prometheus::TEXT_FORMAT;
+
+use crate::mcp_adaptor::McpMetrics;
+use crate::server::ServerHealth;
+
+// Prometheus text format, next to the health endpoints
+pub async fn metrics(State(health): State<ServerHealth>) -> Response {
+    let metrics = &health.context.metrics;
+    metrics.set_active_sessions(&health.context.sessions.count_by_agent().await);
+
+    ([(header::CONTENT_TYPE, TEXT_FORMAT)], metrics.encode()).into_response()
+}
+
+// An sse response is open for as long as its body is, so the stream is counted until the body is dropped
+pub async fn track_sse_streams(State(metrics): State<McpMetrics>, request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+
+    if !is_sse {
+        return response;
+    }
+
+    let stream = metrics.track_sse_stream();
+    let (parts, body) = response.into_parts();
+
+    let body = body.into_data_stream().map(move |chunk| {
+        let _ = &stream;
+        chunk
+    });
+
+    Response::from_parts(parts, Body::from_stream(body))
+}
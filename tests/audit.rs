@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mcp_server::golem::{
+    AgentError, AgentInvocation, AgentInvoker, AgentMethod, AgentMethodHints, AgentMethodTaskSupport, AgentRegistry, AgentTypeDefinition,
+    DataSchemaEntry, ElementSchema, REDACTED,
+};
+use mcp_server::mcp_adaptor::{AuditRecord, AuditSink, GolemAgentMcpServer, McpCallOutcome};
+use serde_json::{json, Value};
+
+use common::{get_context, BlockingInvoker, RawClient};
+
+mod common;
+
+struct StoringInvoker;
+
+#[async_trait]
+impl AgentInvoker for StoringInvoker {
+    async fn invoke(&self, _invocation: AgentInvocation) -> Result<Value, AgentError> {
+        Ok(json!({"result": "stored"}))
+    }
+}
+
+#[derive(Clone, Default)]
+struct RecordingSink {
+    records: Arc<Mutex<Vec<AuditRecord>>>,
+}
+
+#[async_trait]
+impl AuditSink for RecordingSink {
+    async fn record(&self, record: &AuditRecord) -> Result<(), String> {
+        self.records.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+// An agent type with a secret in its constructor and in its method
+fn get_vault_agent_type() -> AgentTypeDefinition {
+    AgentTypeDefinition {
+        constructor_schema: vec![
+            DataSchemaEntry::new("owner", ElementSchema::String),
+            DataSchemaEntry::new("api_key", ElementSchema::String).with_sensitive(true),
+        ],
+        methods: vec![AgentMethod {
+            method_name: "store".into(),
+            title: None,
+            description: None,
+            input_schema: vec![
+                DataSchemaEntry::new("label", ElementSchema::String),
+                DataSchemaEntry::new("secret", ElementSchema::String).with_sensitive(true),
+            ],
+            output_schema: vec![DataSchemaEntry::new("result", ElementSchema::String)],
+            hints: AgentMethodHints::default(),
+            task_support: AgentMethodTaskSupport::Forbidden,
+            exposed_as: vec![],
+        }],
+    }
+}
+
+// A client on the other end of a global server, initialized already
+struct AuditedClient {
+    client: RawClient,
+}
+
+impl AuditedClient {
+    async fn start(invoker: impl AgentInvoker + 'static, sink: RecordingSink) -> Self {
+        let registry = AgentRegistry::new([("vault".to_string(), get_vault_agent_type())].into());
+        let context = get_context(registry, invoker).with_audit(Arc::new(sink));
+
+        AuditedClient {
+            client: RawClient::start(GolemAgentMcpServer::new(None, context), json!({})).await,
+        }
+    }
+
+    async fn call_store(&mut self, id: u32) {
+        self.client.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {
+                "name": "vault__store",
+                "arguments": {"agent": {"owner": "ann", "api_key": "key-123"}, "label": "db", "secret": "hunter2"}
+            }
+        }))
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn audit_record_has_sensitive_parameters_redacted() {
+    let sink = RecordingSink::default();
+    let mut client = AuditedClient::start(StoringInvoker, sink.clone()).await;
+
+    client.call_store(2).await;
+    let response = client.client.receive().await.unwrap();
+    assert_eq!(response["result"]["isError"], false);
+
+    let records = sink.records.lock().unwrap().clone();
+    assert_eq!(records.len(), 1);
+
+    let record = &records[0];
+    assert_eq!(record.agent_id.as_deref(), Some(r#"vault("ann","[redacted]")"#));
+    assert_eq!(record.method_name, "store");
+    assert_eq!(Value::Object(record.arguments.clone()), json!({"label": "db", "secret": REDACTED}));
+    assert_eq!(record.status, McpCallOutcome::Ok);
+    assert_eq!(record.principal, None);
+
+    let serialized = serde_json::to_string(record).unwrap();
+    assert!(!serialized.contains("key-123") && !serialized.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn cancelled_tool_call_is_audited_while_the_session_goes_on() {
+    let sink = RecordingSink::default();
+    let mut client = AuditedClient::start(BlockingInvoker::default(), sink.clone()).await;
+
+    client.call_store(2).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    client
+        .client
+        .send(json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 2}}))
+        .await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let records = sink.records.lock().unwrap().clone();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].status, McpCallOutcome::Cancelled);
+
+    // Still no response to the cancelled call, and the session is still there
+    client.client.send(json!({"jsonrpc": "2.0", "id": 3, "method": "ping"})).await;
+    assert_eq!(client.client.receive().await.unwrap()["id"], 3);
+}
@@ -0,0 +1,172 @@
+// Shared by the integration tests, not every test uses all of it
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mcp_server::golem::{get_counter_agent_type, AgentError, AgentEvents, AgentInvocation, AgentInvoker, AgentRegistry};
+use mcp_server::mcp_adaptor::McpServerContext;
+use rmcp::model::{ClientNotification, ClientRequest, ServerInfo, ServerResult};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{ErrorData, RoleServer, Service};
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream, Lines, ReadHalf, WriteHalf};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+pub const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn get_context(registry: AgentRegistry, invoker: impl AgentInvoker + 'static) -> McpServerContext {
+    McpServerContext::new(registry, Arc::new(invoker), Arc::new(AgentEvents::default()))
+}
+
+// The counter agent type alone, served by the given invoker
+pub fn get_counter_context(invoker: impl AgentInvoker + 'static) -> McpServerContext {
+    get_context(AgentRegistry::new([get_counter_agent_type()].into()), invoker)
+}
+
+// `increment` on the agent, as the adaptor would hand it to the invoker
+pub fn get_increment_invocation(agent_id: &str, cancellation: CancellationToken) -> AgentInvocation {
+    AgentInvocation {
+        agent_id: agent_id.to_string(),
+        method_name: "increment".to_string(),
+        parameters: Map::new(),
+        progress: Default::default(),
+        elicitation: Default::default(),
+        sampling: Default::default(),
+        cancellation,
+        trace_context: None,
+    }
+}
+
+// Never completes on its own, records what the adaptor did to it
+#[derive(Clone, Default)]
+pub struct BlockingInvoker {
+    pub started: Arc<AtomicBool>,
+    pub started_notify: Arc<Notify>,
+    pub cancelled: Arc<AtomicBool>,
+    pub cancelled_notify: Arc<Notify>,
+}
+
+#[async_trait]
+impl AgentInvoker for BlockingInvoker {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError> {
+        self.started.store(true, Ordering::SeqCst);
+        self.started_notify.notify_one();
+
+        let cancelled = self.cancelled.clone();
+        let cancelled_notify = self.cancelled_notify.clone();
+
+        tokio::spawn(async move {
+            invocation.cancellation.cancelled().await;
+            cancelled.store(true, Ordering::SeqCst);
+            cancelled_notify.notify_one();
+        });
+
+        std::future::pending().await
+    }
+}
+
+// Puts the session id header on every message, like rmcp's http transport does, so that the server registers
+// the session and sends it notifications of its own
+pub struct WithSessionId<S> {
+    inner: S,
+    session_id: String,
+}
+
+impl<S> WithSessionId<S> {
+    pub fn new(inner: S, session_id: &str) -> Self {
+        Self {
+            inner,
+            session_id: session_id.to_string(),
+        }
+    }
+
+    fn get_parts(&self) -> http::request::Parts {
+        let (parts, _) = http::Request::builder()
+            .header("mcp-session-id", &self.session_id)
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts
+    }
+}
+
+impl<S: Service<RoleServer>> Service<RoleServer> for WithSessionId<S> {
+    async fn handle_request(&self, request: ClientRequest, mut context: RequestContext<RoleServer>) -> Result<ServerResult, ErrorData> {
+        context.extensions.insert(self.get_parts());
+        self.inner.handle_request(request, context).await
+    }
+
+    async fn handle_notification(&self, notification: ClientNotification, mut context: NotificationContext<RoleServer>) -> Result<(), ErrorData> {
+        context.extensions.insert(self.get_parts());
+        self.inner.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        self.inner.get_info()
+    }
+}
+
+// A client on the other end of a server, over an in-memory transport, speaking json-rpc lines
+pub struct RawClient {
+    lines: Lines<BufReader<ReadHalf<DuplexStream>>>,
+    writer: WriteHalf<DuplexStream>,
+}
+
+impl RawClient {
+    // Serves the server (or a wrapper of it) and goes through the initialize handshake
+    pub async fn start<S: Service<RoleServer>>(server: S, capabilities: Value) -> Self {
+        let (client_stream, server_stream) = tokio::io::duplex(64 * 1024);
+
+        tokio::spawn(async move {
+            if let Ok(running) = rmcp::serve_server(server, server_stream).await {
+                let _ = running.waiting().await;
+            }
+        });
+
+        let (reader, writer) = tokio::io::split(client_stream);
+        let mut client = RawClient {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        };
+
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": capabilities,
+                    "clientInfo": {"name": "test-client", "version": "1.0.0"}
+                }
+            }))
+            .await;
+        let initialized = client.receive().await.expect("no response to initialize");
+        assert_eq!(initialized["id"], 1);
+
+        client
+            .send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await;
+
+        client
+    }
+
+    pub async fn send(&mut self, message: Value) {
+        let line = format!("{}\n", message);
+        self.writer.write_all(line.as_bytes()).await.unwrap();
+    }
+
+    pub async fn receive(&mut self) -> Option<Value> {
+        self.receive_within(RECEIVE_TIMEOUT).await
+    }
+
+    // None if nothing came in time, or the server closed the transport
+    pub async fn receive_within(&mut self, timeout: Duration) -> Option<Value> {
+        let line = tokio::time::timeout(timeout, self.lines.next_line()).await.ok()?;
+        line.unwrap().map(|line| serde_json::from_str(&line).unwrap())
+    }
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use mcp_server::golem::{AgentElicitationResponse, AgentError, AgentInvocation, AgentInvoker, DataSchemaEntry, ElementSchema};
+use mcp_server::mcp_adaptor::GolemAgentMcpServer;
+use serde_json::{json, Value};
+
+use common::{get_counter_context, RawClient};
+
+mod common;
+
+// Asks the user to confirm the increment, and reports what they answered
+struct ElicitingInvoker;
+
+#[async_trait]
+impl AgentInvoker for ElicitingInvoker {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError> {
+        let schema = vec![
+            DataSchemaEntry::new("confirm", ElementSchema::Bool),
+            DataSchemaEntry::new("reason", ElementSchema::String),
+        ];
+
+        let response = invocation
+            .elicitation
+            .elicit("Really increment?".to_string(), schema)
+            .await
+            .map_err(|message| AgentError::Trapped { message })?;
+
+        let answer = match response {
+            AgentElicitationResponse::Accepted(content) => json!({"accepted": content}),
+            AgentElicitationResponse::Declined => json!("declined"),
+            AgentElicitationResponse::Cancelled => json!("cancelled"),
+        };
+
+        Ok(json!({"result": answer}))
+    }
+}
+
+// Answers every `elicitation/create` with the same result, and keeps the requests around
+struct FakeClient {
+    client: RawClient,
+    answer: Value,
+    elicitation_requests: Vec<Value>,
+}
+
+impl FakeClient {
+    async fn start(capabilities: Value, answer: Value) -> Self {
+        let server = GolemAgentMcpServer::new(Some("counter(1)".to_string()), get_counter_context(ElicitingInvoker));
+
+        FakeClient {
+            client: RawClient::start(server, capabilities).await,
+            answer,
+            elicitation_requests: vec![],
+        }
+    }
+
+    async fn call_increment(&mut self) -> Value {
+        self.client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": "increment", "arguments": {"number": 1}}
+            }))
+            .await;
+
+        loop {
+            let message = self.client.receive().await.expect("no response to tools/call");
+
+            if message["method"] == "elicitation/create" {
+                self.client
+                    .send(json!({"jsonrpc": "2.0", "id": message["id"], "result": self.answer}))
+                    .await;
+                self.elicitation_requests.push(message);
+            } else if message["id"] == 2 {
+                return message["result"].clone();
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn accepted_content_is_returned_to_the_agent() {
+    let answer = json!({"action": "accept", "content": {"confirm": true, "reason": "why not"}});
+    let mut client = FakeClient::start(json!({"elicitation": {}}), answer).await;
+
+    let result = client.call_increment().await;
+
+    assert_eq!(
+        result["structuredContent"],
+        json!({"result": {"accepted": {"confirm": true, "reason": "why not"}}})
+    );
+
+    assert_eq!(client.elicitation_requests.len(), 1);
+    let params = &client.elicitation_requests[0]["params"];
+    assert_eq!(params["message"], "Really increment?");
+    assert_eq!(params["requestedSchema"]["required"], json!(["confirm", "reason"]));
+}
+
+#[tokio::test]
+async fn accepted_content_that_does_not_fit_the_schema_fails_the_call() {
+    let answer = json!({"action": "accept", "content": {"confirm": "yes"}});
+    let mut client = FakeClient::start(json!({"elicitation": {}}), answer).await;
+
+    let result = client.call_increment().await;
+
+    assert_eq!(result["isError"], true);
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("elicitation response has"));
+}
+
+#[tokio::test]
+async fn declined_and_cancelled_are_told_apart() {
+    for (action, expected) in [("decline", "declined"), ("cancel", "cancelled")] {
+        let mut client = FakeClient::start(json!({"elicitation": {}}), json!({"action": action})).await;
+
+        let result = client.call_increment().await;
+
+        assert_eq!(result["structuredContent"], json!({"result": expected}));
+    }
+}
+
+#[tokio::test]
+async fn elicitation_is_disabled_without_client_capability() {
+    let mut client = FakeClient::start(json!({}), json!({"action": "accept"})).await;
+
+    let result = client.call_increment().await;
+
+    assert_eq!(result["isError"], true);
+    assert_eq!(result["content"][0]["text"], "agent failed: the client does not support elicitation");
+    assert!(client.elicitation_requests.is_empty());
+}
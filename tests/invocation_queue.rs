@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use mcp_server::golem::{AgentError, AgentInvocation};
+use mcp_server::mcp_adaptor::{McpInvocationLimits, McpInvocationQueue, McpMetrics, QueueOverflow};
+use tokio_util::sync::CancellationToken;
+
+use common::{get_increment_invocation, BlockingInvoker};
+
+mod common;
+
+fn invocation(cancellation: &CancellationToken) -> AgentInvocation {
+    get_increment_invocation("counter(1)", cancellation.clone())
+}
+
+fn queue(limits: McpInvocationLimits) -> McpInvocationQueue {
+    McpInvocationQueue::new(limits, McpMetrics::default())
+}
+
+// Lets the first invocation take its place in the queue, and keeps it there until the token is cancelled
+fn start_blocking(queue: &McpInvocationQueue) -> CancellationToken {
+    let cancellation = CancellationToken::new();
+    let queue = queue.clone();
+    let invocation = invocation(&cancellation);
+    tokio::spawn(async move { queue.invoke(&BlockingInvoker::default(), invocation).await });
+    cancellation
+}
+
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(20)).await;
+}
+
+fn is_empty(queue: &McpInvocationQueue) -> bool {
+    ["counter(1)", "counter(2)"].iter().all(|agent_id| queue.get_pending(&agent_id.to_string()) == 0)
+}
+
+#[tokio::test]
+async fn rejected_invocation_leaves_no_queue_behind() {
+    let queue = queue(McpInvocationLimits {
+        max_queue_depth: Some(1),
+        ..Default::default()
+    });
+    let first = start_blocking(&queue);
+    settle().await;
+
+    let result = queue.invoke(&BlockingInvoker::default(), invocation(&CancellationToken::new())).await;
+    assert!(matches!(result, Some(Err(AgentError::Overloaded { .. }))));
+
+    first.cancel();
+    settle().await;
+    assert!(is_empty(&queue));
+}
+
+#[tokio::test]
+async fn timed_out_invocation_leaves_no_queue_behind() {
+    let queue = queue(McpInvocationLimits {
+        serialize_per_agent: true,
+        overflow: QueueOverflow::Wait { timeout_ms: 50 },
+        ..Default::default()
+    });
+    let first = start_blocking(&queue);
+    settle().await;
+
+    // There is room in the queue, it's the turn that never comes
+    let result = queue.invoke(&BlockingInvoker::default(), invocation(&CancellationToken::new())).await;
+    assert!(matches!(result, Some(Err(AgentError::Overloaded { .. }))));
+
+    first.cancel();
+    settle().await;
+    assert!(is_empty(&queue));
+}
+
+#[tokio::test]
+async fn global_cap_wait_is_bounded_by_the_timeout() {
+    let queue = queue(McpInvocationLimits {
+        max_concurrent: Some(1),
+        overflow: QueueOverflow::Wait { timeout_ms: 50 },
+        ..Default::default()
+    });
+    let first = start_blocking(&queue);
+    settle().await;
+
+    let mut other = invocation(&CancellationToken::new());
+    other.agent_id = "counter(2)".to_string();
+    let result = queue.invoke(&BlockingInvoker::default(), other).await;
+    assert!(matches!(result, Some(Err(AgentError::Overloaded { .. }))));
+
+    first.cancel();
+    settle().await;
+    assert!(is_empty(&queue));
+}
+
+#[tokio::test]
+async fn cancelled_waiting_invocation_leaves_no_queue_behind() {
+    let queue = queue(McpInvocationLimits {
+        serialize_per_agent: true,
+        ..Default::default()
+    });
+    let first = start_blocking(&queue);
+    let second = start_blocking(&queue);
+    settle().await;
+
+    second.cancel();
+    settle().await;
+    assert_eq!(queue.get_pending(&"counter(1)".to_string()), 1);
+
+    first.cancel();
+    settle().await;
+    assert!(is_empty(&queue));
+}
+
+#[tokio::test]
+async fn rejected_invocation_does_not_wait_for_its_turn() {
+    let queue = queue(McpInvocationLimits {
+        max_queue_depth: Some(8),
+        serialize_per_agent: true,
+        ..Default::default()
+    });
+    let first = start_blocking(&queue);
+    settle().await;
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(1),
+        queue.invoke(&BlockingInvoker::default(), invocation(&CancellationToken::new())),
+    )
+    .await
+    .expect("rejected invocations don't wait");
+    assert!(matches!(result, Some(Err(AgentError::Overloaded { .. }))));
+
+    first.cancel();
+    settle().await;
+    assert!(is_empty(&queue));
+}
+
+#[tokio::test]
+async fn rejected_invocation_does_not_wait_for_the_global_cap() {
+    let queue = queue(McpInvocationLimits {
+        max_concurrent: Some(1),
+        ..Default::default()
+    });
+    let first = start_blocking(&queue);
+    settle().await;
+
+    let mut other = invocation(&CancellationToken::new());
+    other.agent_id = "counter(2)".to_string();
+    let result = tokio::time::timeout(Duration::from_secs(1), queue.invoke(&BlockingInvoker::default(), other))
+        .await
+        .expect("rejected invocations don't wait");
+    assert!(matches!(result, Some(Err(AgentError::Overloaded { .. }))));
+
+    first.cancel();
+    settle().await;
+    assert!(is_empty(&queue));
+}
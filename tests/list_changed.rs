@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mcp_server::golem::{
+    get_counter_agent_type, AgentEvents, AgentMethod, AgentMethodHints, AgentMethodTaskSupport, AgentRegistry, AgentType,
+    AgentTypeDefinition, DataSchemaEntry, ElementSchema,
+};
+use mcp_server::mcp_adaptor::{GolemAgentMcpServer, McpServerContext};
+use serde_json::{json, Value};
+
+use common::{BlockingInvoker, RawClient, WithSessionId};
+
+mod common;
+
+// Long enough for the session to take a change in, debounce included
+const QUIET: Duration = Duration::from_millis(300);
+
+fn get_method(method_name: &str, input_schema: Vec<DataSchemaEntry>) -> AgentMethod {
+    AgentMethod {
+        method_name: method_name.into(),
+        title: None,
+        description: None,
+        input_schema,
+        output_schema: vec![DataSchemaEntry::new("result", ElementSchema::U32)],
+        hints: AgentMethodHints::default(),
+        task_support: AgentMethodTaskSupport::Forbidden,
+        exposed_as: vec![],
+    }
+}
+
+// A tool and a resource (the method without parameters)
+fn get_thermostat_agent_type() -> (AgentType, AgentTypeDefinition) {
+    (
+        "thermostat".into(),
+        AgentTypeDefinition {
+            constructor_schema: vec![DataSchemaEntry::new("id", ElementSchema::U32)],
+            methods: vec![
+                get_method("set", vec![DataSchemaEntry::new("degrees", ElementSchema::U32)]),
+                get_method("get_value", vec![]),
+            ],
+        },
+    )
+}
+
+async fn start(agent_id: Option<&str>, registry: AgentRegistry, agent_events: Arc<AgentEvents>) -> RawClient {
+    let context = McpServerContext::new(registry, Arc::new(BlockingInvoker::default()), agent_events);
+    let server = GolemAgentMcpServer::new(agent_id.map(ToString::to_string), context.clone());
+
+    let client = RawClient::start(WithSessionId::new(server, "session-1"), json!({})).await;
+
+    // The session is registered right before it starts listening for changes
+    while context.sessions.get("session-1").await.is_none() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    client
+}
+
+// Every notification until the server goes quiet
+async fn receive_notifications(client: &mut RawClient) -> Vec<Value> {
+    let mut notifications = vec![];
+
+    while let Some(message) = client.receive_within(QUIET).await {
+        notifications.push(message["method"].clone());
+    }
+
+    notifications
+}
+
+#[tokio::test]
+async fn a_reload_sends_each_changed_list_once() {
+    let registry = AgentRegistry::new([get_counter_agent_type()].into());
+    let mut client = start(None, registry.clone(), Arc::default()).await;
+
+    let (_, mut clock_definition) = get_thermostat_agent_type();
+    clock_definition.methods.truncate(1);
+    registry.replace_all([get_counter_agent_type(), get_thermostat_agent_type(), ("clock".into(), clock_definition)].into());
+
+    // No prompts among the new methods
+    let mut notifications = receive_notifications(&mut client).await;
+    notifications.sort_by_key(|method| method.to_string());
+    assert_eq!(
+        notifications,
+        vec!["notifications/resources/list_changed", "notifications/tools/list_changed"]
+    );
+}
+
+#[tokio::test]
+async fn only_changes_to_the_sessions_own_lists_are_reported() {
+    let registry = AgentRegistry::new([get_counter_agent_type()].into());
+    let mut client = start(Some("counter(1)"), registry.clone(), Arc::default()).await;
+
+    // Another agent type altogether
+    registry.deploy("thermostat".into(), get_thermostat_agent_type().1);
+    assert_eq!(receive_notifications(&mut client).await, Vec::<Value>::new());
+
+    // The counter's tool changes, it has no resources or prompts
+    let (counter, mut definition) = get_counter_agent_type();
+    definition.methods[0].description = Some("Adds to the counter".into());
+    registry.deploy(counter, definition);
+    assert_eq!(receive_notifications(&mut client).await, vec!["notifications/tools/list_changed"]);
+}
+
+#[tokio::test]
+async fn subscribed_resources_are_reported_updated() {
+    let registry = AgentRegistry::new([get_thermostat_agent_type()].into());
+    let agent_events = Arc::new(AgentEvents::default());
+    let mut client = start(Some("thermostat(1)"), registry, agent_events.clone()).await;
+    let uri = "golem://agents/thermostat(1)/get_value";
+
+    client
+        .send(json!({"jsonrpc": "2.0", "id": 2, "method": "resources/subscribe", "params": {"uri": uri}}))
+        .await;
+    assert_eq!(client.receive().await.unwrap()["id"], 2);
+
+    agent_events.state_changed("thermostat(2)".to_string());
+    agent_events.state_changed("thermostat(1)".to_string());
+
+    let updated = client.receive().await.expect("no resources/updated notification");
+    assert_eq!(updated["method"], "notifications/resources/updated");
+    assert_eq!(updated["params"]["uri"], uri);
+    assert_eq!(client.receive_within(QUIET).await, None);
+
+    // Not after unsubscribing
+    client
+        .send(json!({"jsonrpc": "2.0", "id": 3, "method": "resources/unsubscribe", "params": {"uri": uri}}))
+        .await;
+    assert_eq!(client.receive().await.unwrap()["id"], 3);
+
+    agent_events.state_changed("thermostat(1)".to_string());
+    assert_eq!(client.receive_within(QUIET).await, None);
+}
@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use mcp_server::golem::{AgentError, AgentInvocation, AgentInvoker};
+use mcp_server::mcp_adaptor::{GolemAgentMcpServer, McpLoggingLayer};
+use serde_json::{json, Value};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use common::{get_counter_context, RawClient};
+
+mod common;
+
+// Logs on the way, like a backend that reports what it is doing
+struct ChattyInvoker;
+
+#[async_trait]
+impl AgentInvoker for ChattyInvoker {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError> {
+        tracing::debug!(agent_id = %invocation.agent_id, "looking up the counter");
+        tracing::info!(agent_id = %invocation.agent_id, "incrementing the counter");
+        Ok(json!({"result": 1}))
+    }
+}
+
+// A bare server, with no wrapper around it, still forwards the events of a tool call once the session sets a level.
+// The test runtime is single threaded, so the subscriber set here also sees the server's tasks
+#[tokio::test]
+async fn tool_call_events_are_forwarded_from_the_set_level_on() {
+    let context = get_counter_context(ChattyInvoker);
+
+    let _subscriber = tracing_subscriber::registry()
+        .with(McpLoggingLayer::new(context.loggers.clone()))
+        .set_default();
+
+    let mut client = RawClient::start(GolemAgentMcpServer::new(None, context), json!({})).await;
+
+    client
+        .send(json!({"jsonrpc": "2.0", "id": 2, "method": "logging/setLevel", "params": {"level": "info"}}))
+        .await;
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": {"name": "counter__increment", "arguments": {"agent": {"id": 1}, "number": 1}}
+        }))
+        .await;
+
+    let mut logged = vec![];
+    let mut call_answered = false;
+
+    while !call_answered || logged.is_empty() {
+        let message = client.receive().await.expect("no tool call response or log message");
+
+        if message["method"] == "notifications/message" {
+            logged.push(message["params"].clone());
+        } else if message["id"] == 3 {
+            assert_eq!(message["result"]["isError"], false);
+            call_answered = true;
+        }
+    }
+
+    // The debug event is below the session's level
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0]["level"], "info");
+    assert_eq!(logged[0]["data"]["message"], "incrementing the counter");
+}
@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use mcp_server::golem::{AgentError, AgentInvocation, AgentInvoker};
+use mcp_server::mcp_adaptor::{GolemAgentMcpServer, McpMeteredServer};
+use serde_json::{json, Value};
+
+use common::{get_counter_context, RawClient};
+
+mod common;
+
+struct CountingInvoker;
+
+#[async_trait]
+impl AgentInvoker for CountingInvoker {
+    async fn invoke(&self, _invocation: AgentInvocation) -> Result<Value, AgentError> {
+        Ok(json!({"result": 1}))
+    }
+}
+
+#[tokio::test]
+async fn one_tool_call_is_counted_and_timed() {
+    let context = get_counter_context(CountingInvoker);
+    let metrics = context.metrics.clone();
+
+    let server = McpMeteredServer::new(GolemAgentMcpServer::new(None, context), metrics.clone());
+    let mut client = RawClient::start(server, json!({})).await;
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "counter__increment", "arguments": {"agent": {"id": 1}, "number": 1}}
+        }))
+        .await;
+
+    let response = client.receive().await.expect("no tool call response");
+    assert_eq!(response["result"]["isError"], false);
+
+    let output = metrics.encode();
+
+    // The initialize handshake goes through the wrapper as well
+    for line in [
+        r#"mcp_requests_total{method="initialize",outcome="ok"} 1"#,
+        r#"mcp_requests_total{method="tools/call",outcome="ok"} 1"#,
+        r#"mcp_request_duration_seconds_count{method="tools/call"} 1"#,
+        r#"mcp_tool_calls_total{agent_type="counter",outcome="ok",tool="counter__increment"} 1"#,
+        r#"mcp_tool_call_duration_seconds_count{agent_type="counter",tool="counter__increment"} 1"#,
+    ] {
+        assert!(output.lines().any(|l| l == line), "no `{}` in\n{}", line, output);
+    }
+}
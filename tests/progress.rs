@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mcp_server::golem::{AgentError, AgentInvocation, AgentInvoker};
+use mcp_server::mcp_adaptor::{GolemAgentMcpServer, PROGRESS_NOTIFICATION_INTERVAL};
+use serde_json::{json, Value};
+
+use common::{get_counter_context, RawClient};
+
+mod common;
+
+// Reports `number` steps of progress, waiting `step_ms` after each
+struct ChattyInvoker {
+    step_ms: u64,
+}
+
+#[async_trait]
+impl AgentInvoker for ChattyInvoker {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError> {
+        let steps = invocation.parameters.get("number").and_then(Value::as_u64).unwrap_or_default();
+
+        for step in 1..=steps {
+            invocation.progress.report(step as f64, Some(steps as f64), None);
+            tokio::time::sleep(Duration::from_millis(self.step_ms)).await;
+        }
+
+        Ok(json!({"result": steps}))
+    }
+}
+
+// The progress notifications that came before the response, with when they came
+async fn call_increment(step_ms: u64, number: u32, meta: Value) -> Vec<(tokio::time::Instant, Value)> {
+    let server = GolemAgentMcpServer::new(Some("counter(1)".to_string()), get_counter_context(ChattyInvoker { step_ms }));
+    let mut client = RawClient::start(server, json!({})).await;
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "increment", "arguments": {"number": number}, "_meta": meta}
+        }))
+        .await;
+
+    let mut notifications = vec![];
+
+    loop {
+        let message = client.receive().await.expect("no response to tools/call");
+
+        if message["method"] == "notifications/progress" {
+            notifications.push((tokio::time::Instant::now(), message["params"].clone()));
+        } else if message["id"] == 2 {
+            assert_eq!(message["result"]["isError"], false);
+            return notifications;
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn updates_in_between_are_coalesced() {
+    // 20 steps of 10ms, about one notification per interval
+    let notifications = call_increment(10, 20, json!({"progressToken": "t1"})).await;
+
+    assert!((2..=4).contains(&notifications.len()), "{:?}", notifications);
+
+    for window in notifications.windows(2) {
+        assert!(window[1].1["progress"].as_f64() > window[0].1["progress"].as_f64());
+    }
+
+    // The last one may go out as soon as the call is done, the others are an interval apart
+    let throttled = &notifications[..notifications.len() - 1];
+    for window in throttled.windows(2) {
+        assert!(window[1].0 - window[0].0 >= PROGRESS_NOTIFICATION_INTERVAL);
+    }
+
+    assert_eq!(notifications.iter().map(|(_, params)| &params["progressToken"]).collect::<Vec<_>>(), vec!["t1"; notifications.len()]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_final_update_is_always_sent() {
+    // Everything is reported within one interval
+    let notifications = call_increment(0, 5, json!({"progressToken": "t1"})).await;
+
+    let (_, last) = notifications.last().expect("no progress notification");
+    assert_eq!(last["progress"], 5.0);
+    assert_eq!(last["total"], 5.0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn nothing_is_sent_without_a_progress_token() {
+    let notifications = call_increment(10, 20, json!({})).await;
+
+    assert!(notifications.is_empty());
+}
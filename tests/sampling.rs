@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use mcp_server::golem::{
+    AgentError, AgentInvocation, AgentInvoker, AgentModelPreferences, AgentSamplingMessage, AgentSamplingRequest, AgentSamplingRole,
+};
+use mcp_server::mcp_adaptor::GolemAgentMcpServer;
+use serde_json::{json, Value};
+
+use common::{get_counter_context, RawClient};
+
+mod common;
+
+// Asks the client's llm to describe the number it got
+struct SamplingInvoker;
+
+#[async_trait]
+impl AgentInvoker for SamplingInvoker {
+    async fn invoke(&self, invocation: AgentInvocation) -> Result<Value, AgentError> {
+        let number = invocation.parameters.get("number").cloned().unwrap_or_default();
+
+        let response = invocation
+            .sampling
+            .sample(AgentSamplingRequest {
+                messages: vec![AgentSamplingMessage {
+                    role: AgentSamplingRole::User,
+                    text: format!("describe {}", number),
+                }],
+                system_prompt: Some("You are a counter".to_string()),
+                model_preferences: Some(AgentModelPreferences {
+                    hints: vec!["small-model".to_string()],
+                    speed_priority: Some(1.0),
+                    ..Default::default()
+                }),
+                temperature: None,
+                max_tokens: 64,
+            })
+            .await
+            .map_err(|message| AgentError::Trapped { message })?;
+
+        Ok(json!({"result": response.text, "model": response.model}))
+    }
+}
+
+// Answers every `sampling/createMessage` by echoing the last message back, and keeps the requests around
+struct FakeClient {
+    client: RawClient,
+    sampling_requests: Vec<Value>,
+}
+
+impl FakeClient {
+    async fn start(capabilities: Value) -> Self {
+        let server = GolemAgentMcpServer::new(Some("counter(1)".to_string()), get_counter_context(SamplingInvoker));
+
+        FakeClient {
+            client: RawClient::start(server, capabilities).await,
+            sampling_requests: vec![],
+        }
+    }
+
+    async fn call_increment(&mut self, number: u32) -> Value {
+        self.client.send(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "increment", "arguments": {"number": number}}
+        }))
+        .await;
+
+        loop {
+            let message = self.client.receive().await.expect("no response to tools/call");
+
+            if message["method"] == "sampling/createMessage" {
+                let text = message["params"]["messages"][0]["content"]["text"].as_str().unwrap().to_string();
+                self.client.send(json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "result": {
+                        "model": "fake-model",
+                        "stopReason": "endTurn",
+                        "role": "assistant",
+                        "content": {"type": "text", "text": format!("echo: {}", text)}
+                    }
+                }))
+                .await;
+                self.sampling_requests.push(message);
+            } else if message["id"] == 2 {
+                return message["result"].clone();
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn sampling_result_is_returned_to_the_agent() {
+    let mut client = FakeClient::start(json!({"sampling": {}})).await;
+
+    let result = client.call_increment(7).await;
+
+    assert_eq!(
+        result["structuredContent"],
+        json!({"result": "echo: describe 7", "model": "fake-model"})
+    );
+
+    assert_eq!(client.sampling_requests.len(), 1);
+    let params = &client.sampling_requests[0]["params"];
+    assert_eq!(params["systemPrompt"], "You are a counter");
+    assert_eq!(params["maxTokens"], 64);
+    assert_eq!(
+        params["modelPreferences"],
+        json!({"hints": [{"name": "small-model"}], "speedPriority": 1.0})
+    );
+}
+
+#[tokio::test]
+async fn sampling_is_disabled_without_client_capability() {
+    let mut client = FakeClient::start(json!({})).await;
+
+    let result = client.call_increment(7).await;
+
+    assert_eq!(result["isError"], true);
+    assert_eq!(result["content"][0]["text"], "agent failed: the client does not support sampling");
+    assert!(client.sampling_requests.is_empty());
+}
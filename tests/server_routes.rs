@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use http_body_util::BodyExt;
+use mcp_server::golem::{
+    get_counter_agent_type, AgentError, AgentInvocation, AgentInvoker, AgentRegistry, AgentTypeDefinition, DataSchemaEntry, ElementSchema,
+};
+use mcp_server::mcp_adaptor::McpServerContext;
+use mcp_server::server::{build_router, BearerTokenConfig, RouteLayout, ServerConfig, ServerHealth};
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use common::{get_context, get_counter_context, BlockingInvoker};
+
+mod common;
+
+const TOKEN: &str = "change-me";
+
+// Never reaches the backend
+struct UnreachableInvoker;
+
+#[async_trait]
+impl AgentInvoker for UnreachableInvoker {
+    async fn invoke(&self, _invocation: AgentInvocation) -> Result<Value, AgentError> {
+        unreachable!()
+    }
+
+    async fn check_health(&self) -> Result<(), AgentError> {
+        Err(AgentError::BackendUnavailable {
+            message: "connection refused".to_string(),
+        })
+    }
+}
+
+fn get_config(with_auth: bool) -> ServerConfig {
+    let mut config = ServerConfig::default();
+
+    if with_auth {
+        config.auth.bearer_tokens = vec![BearerTokenConfig {
+            principal: "alice".to_string(),
+            token: TOKEN.to_string(),
+        }];
+    }
+
+    config
+}
+
+fn get_router(config: &ServerConfig, context: McpServerContext) -> axum::Router {
+    let health = ServerHealth {
+        context: context.clone(),
+        manifest: None,
+    };
+
+    build_router(config, context, health, CancellationToken::new())
+}
+
+async fn get(router: &axum::Router, path: &str, token: Option<&str>) -> (StatusCode, Value) {
+    let mut request = Request::get(path);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+
+    let response = router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn post(router: &axum::Router, path: &str, session_id: Option<&str>, message: Value) -> axum::response::Response {
+    let mut request = Request::post(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT, "application/json, text/event-stream");
+    if let Some(session_id) = session_id {
+        request = request.header("mcp-session-id", session_id);
+    }
+
+    router.clone().oneshot(request.body(Body::from(message.to_string())).unwrap()).await.unwrap()
+}
+
+#[tokio::test]
+async fn health_checks_are_served_without_a_token() {
+    let router = get_router(&get_config(true), get_counter_context(BlockingInvoker::default()));
+
+    let (status, body) = get(&router, "/healthz", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = get(&router, "/readyz", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+}
+
+#[tokio::test]
+async fn not_ready_without_agent_types_or_backend() {
+    let router = get_router(&get_config(false), get_context(AgentRegistry::new(HashMap::new()), UnreachableInvoker));
+
+    let (status, body) = get(&router, "/readyz", None).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"]["registry"]["ok"], false);
+    assert_eq!(body["checks"]["invoker"]["ok"], false);
+}
+
+#[tokio::test]
+async fn info_and_metrics_take_a_token_unless_made_public() {
+    let router = get_router(&get_config(true), get_counter_context(BlockingInvoker::default()));
+
+    for path in ["/info", "/metrics"] {
+        assert_eq!(get(&router, path, None).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(get(&router, path, Some(TOKEN)).await.0, StatusCode::OK);
+    }
+
+    let mut config = get_config(true);
+    config.auth.public_info_and_metrics = true;
+    let router = get_router(&config, get_counter_context(BlockingInvoker::default()));
+
+    for path in ["/info", "/metrics"] {
+        assert_eq!(get(&router, path, None).await.0, StatusCode::OK);
+    }
+
+    // The mcp routes still take one
+    let response = post(&router, "/mcp", None, json!({})).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn info_redacts_the_agent_ids_of_sessions() {
+    let vault = AgentTypeDefinition {
+        constructor_schema: vec![
+            DataSchemaEntry::new("owner", ElementSchema::String),
+            DataSchemaEntry::new("api_key", ElementSchema::String).with_sensitive(true),
+        ],
+        methods: vec![],
+    };
+    let registry = AgentRegistry::new([get_counter_agent_type(), ("vault".into(), vault)].into());
+
+    let mut config = get_config(false);
+    config.routes.layout = RouteLayout::PerAgent;
+    let router = get_router(&config, get_context(registry, BlockingInvoker::default()));
+
+    // Two sessions of agents that only differ in their secret
+    for secret in ["first-secret", "second-secret"] {
+        let path = format!("/mcp/vault(%22ann%22,%22{}%22)", secret);
+
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test-client", "version": "1.0.0"}
+            }
+        });
+        let response = post(&router, &path, None, initialize).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let session_id = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+
+        let initialized = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let response = post(&router, &path, Some(&session_id), initialized).await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+
+    // The session is registered once the server has seen the notification
+    let mut body = Value::Null;
+    for _ in 0..50 {
+        body = get(&router, "/info", None).await.1;
+        if body["sessions"]["total"] == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    assert_eq!(body["sessions"]["per_agent"], json!({r#"vault("ann","[redacted]")"#: 2}));
+    assert!(!body.to_string().contains("secret"), "{}", body);
+}
@@ -0,0 +1,192 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use mcp_server::golem::{get_counter_agent_type, AgentInvocation, AgentMethodTaskSupport, AgentRegistry, AgentType, AgentTypeDefinition};
+use mcp_server::mcp_adaptor::{invoke_cancellable, GolemAgentMcpServer, MAX_PARKED_REQUESTS};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use common::{get_context, get_increment_invocation, BlockingInvoker, RawClient};
+
+mod common;
+
+fn invocation(cancellation: CancellationToken) -> AgentInvocation {
+    get_increment_invocation("counter(1)", cancellation)
+}
+
+#[tokio::test]
+async fn cancel_before_start_never_reaches_the_backend() {
+    let invoker = BlockingInvoker::default();
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+
+    let result = invoke_cancellable(&invoker, invocation(cancellation)).await;
+
+    assert!(result.is_none());
+    assert!(!invoker.started.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn cancel_mid_flight_signals_the_backend() {
+    let invoker = BlockingInvoker::default();
+    let cancellation = CancellationToken::new();
+
+    let call = tokio::spawn({
+        let invoker = invoker.clone();
+        let cancellation = cancellation.clone();
+        async move { invoke_cancellable(&invoker, invocation(cancellation)).await }
+    });
+
+    invoker.started_notify.notified().await;
+    cancellation.cancel();
+
+    let result = tokio::time::timeout(Duration::from_secs(2), call).await.unwrap().unwrap();
+
+    assert!(result.is_none());
+    tokio::time::timeout(Duration::from_secs(2), invoker.cancelled_notify.notified())
+        .await
+        .unwrap();
+    assert!(invoker.cancelled.load(Ordering::SeqCst));
+}
+
+async fn start_server(invoker: BlockingInvoker) -> RawClient {
+    start_server_with(invoker, get_counter_agent_type()).await.0
+}
+
+// Also hands out the session token, to see when the server considers the session over
+async fn start_server_with(invoker: BlockingInvoker, agent_type: (AgentType, AgentTypeDefinition)) -> (RawClient, CancellationToken) {
+    let context = get_context(AgentRegistry::new([agent_type].into()), invoker);
+    let server = GolemAgentMcpServer::new(Some("counter(1)".to_string()), context);
+    let session_ct = server.session_ct.clone();
+
+    (RawClient::start(server, json!({})).await, session_ct)
+}
+
+#[tokio::test]
+async fn cancelled_tool_call_gets_no_response() {
+    let invoker = BlockingInvoker::default();
+    let mut client = start_server(invoker.clone()).await;
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "increment", "arguments": {"number": 1}}
+        }))
+        .await;
+
+    tokio::time::timeout(Duration::from_secs(2), invoker.started_notify.notified())
+        .await
+        .unwrap();
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": 2, "reason": "user gave up"}
+        }))
+        .await;
+
+    tokio::time::timeout(Duration::from_secs(2), invoker.cancelled_notify.notified())
+        .await
+        .unwrap();
+
+    assert_eq!(client.receive_within(Duration::from_millis(500)).await, None);
+}
+
+#[tokio::test]
+async fn cancelled_task_result_gets_no_response_and_is_released_with_the_session() {
+    let invoker = BlockingInvoker::default();
+    let (agent_type, mut definition) = get_counter_agent_type();
+    definition.methods[0].task_support = AgentMethodTaskSupport::Optional;
+    let (mut client, session_ct) = start_server_with(invoker.clone(), (agent_type, definition)).await;
+
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "tools/call",
+            "params": {"name": "increment", "arguments": {"number": 1}, "task": {}}
+        }))
+        .await;
+    let created = client.receive().await.unwrap();
+    let task_id = created["result"]["task"]["taskId"].as_str().unwrap().to_string();
+
+    tokio::time::timeout(Duration::from_secs(2), invoker.started_notify.notified())
+        .await
+        .unwrap();
+
+    client
+        .send(json!({"jsonrpc": "2.0", "id": 3, "method": "tasks/result", "params": {"taskId": task_id}}))
+        .await;
+    client
+        .send(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": 3, "reason": "user gave up"}
+        }))
+        .await;
+
+    assert_eq!(client.receive_within(Duration::from_millis(500)).await, None);
+
+    // The session goes on as usual
+    client.send(json!({"jsonrpc": "2.0", "id": 4, "method": "ping"})).await;
+    assert_eq!(client.receive().await.unwrap()["id"], 4);
+
+    // The parked handler holds on to the server, it's the closed transport that ends the session
+    drop(client);
+    tokio::time::timeout(Duration::from_millis(500), session_ct.cancelled())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn cancelled_requests_past_the_limit_are_answered_after_all() {
+    let context = get_context(AgentRegistry::new([get_counter_agent_type()].into()), BlockingInvoker::default());
+    let metrics = context.metrics.clone();
+    let server = GolemAgentMcpServer::new(Some("counter(1)".to_string()), context);
+    let session_ct = server.session_ct.clone();
+    let mut client = RawClient::start(server, json!({})).await;
+
+    let calls = MAX_PARKED_REQUESTS + 1;
+    for id in 0..calls {
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "id": id + 2,
+                "method": "tools/call",
+                "params": {"name": "increment", "arguments": {"number": 1}}
+            }))
+            .await;
+        client
+            .send(json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/cancelled",
+                "params": {"requestId": id + 2, "reason": "user gave up"}
+            }))
+            .await;
+    }
+
+    // Whichever came last to park
+    let response = client.receive().await.expect("no response past the limit");
+    assert_eq!(response["error"]["message"], "tool call cancelled");
+    assert_eq!(client.receive_within(Duration::from_millis(500)).await, None);
+
+    let parked = format!("mcp_parked_requests {}", MAX_PARKED_REQUESTS);
+    assert!(metrics.encode().lines().any(|line| line == parked));
+
+    drop(client);
+    tokio::time::timeout(Duration::from_millis(500), session_ct.cancelled())
+        .await
+        .unwrap();
+
+    // The parked handlers return (into a closed transport) with the session over
+    for _ in 0..50 {
+        if metrics.encode().lines().any(|line| line == "mcp_parked_requests 0") {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("parked requests not released");
+}